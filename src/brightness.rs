@@ -0,0 +1,154 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use tracing::warn;
+
+/// N. of brightness levels the screen can be set to with the BRT and DIM keys
+pub const BRIGHTNESS_STEPS: u8 = 10;
+
+/// Represents the brightness of the screen, from 1 (the dimmest level, still readable) to
+/// `BRIGHTNESS_STEPS` (full brightness)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Brightness(u8);
+
+impl Default for Brightness {
+    fn default() -> Self {
+        Brightness::MAX
+    }
+}
+
+impl Brightness {
+    pub const MIN: Brightness = Brightness(1);
+    pub const MAX: Brightness = Brightness(BRIGHTNESS_STEPS);
+
+    /// Creates the brightness of the given level, clamped to the levels available
+    pub fn new(level: u8) -> Self {
+        Self(level.clamp(Self::MIN.0, Self::MAX.0))
+    }
+
+    pub fn level(self) -> u8 {
+        self.0
+    }
+
+    /// Returns the brightness one level above, as the BRT key does
+    pub fn brighter(self) -> Self {
+        Self::new(self.0.saturating_add(1))
+    }
+
+    /// Returns the brightness one level below, as the DIM key does
+    pub fn dimmer(self) -> Self {
+        Self::new(self.0.saturating_sub(1))
+    }
+
+    /// Returns the brightness as a fraction of the full brightness
+    pub fn factor(self) -> f32 {
+        f32::from(self.0) / f32::from(Self::MAX.0)
+    }
+
+    /// Loads the brightness saved in the given file. Defaults to full brightness when the file is
+    /// missing or invalid, so that the screen is never left unreadable
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(level) => match level.trim().parse() {
+                Ok(level) => Self::new(level),
+                Err(_) => {
+                    warn!("Invalid brightness {:?} in {}", level, path.display());
+                    Self::MAX
+                }
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Self::MAX,
+            Err(err) => {
+                warn!(
+                    "Cannot read the brightness from {}: {}",
+                    path.display(),
+                    err
+                );
+                Self::MAX
+            }
+        }
+    }
+
+    /// Saves the brightness to the given file, to be restored on the next start
+    pub fn save(self, path: &Path) -> io::Result<()> {
+        fs::write(path, format!("{}\n", self.0))
+    }
+}
+
+/// Drives the backlight of the panel through the Linux sysfs interface, given the directory of
+/// the device (e.g. "/sys/class/backlight/rpi_backlight")
+#[derive(Debug)]
+pub struct Backlight {
+    path: PathBuf,
+    max_brightness: u32,
+}
+
+impl Backlight {
+    /// Opens the backlight device, reading the highest brightness it supports
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let max_brightness = fs::read_to_string(path.join("max_brightness"))?
+            .trim()
+            .parse()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        Ok(Self {
+            path: path.into(),
+            max_brightness,
+        })
+    }
+
+    /// Sets the backlight to the given brightness, scaled to the range supported by the device
+    pub fn set(&self, brightness: Brightness) -> io::Result<()> {
+        let value = (self.max_brightness as f32 * brightness.factor()).round() as u32;
+        fs::write(self.path.join("brightness"), value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// Creates an empty directory to be used by a single test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mcdu-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn brightness_is_clamped_and_persisted() {
+        assert_eq!(Brightness::MAX.brighter(), Brightness::MAX);
+        assert_eq!(Brightness::MIN.dimmer(), Brightness::MIN);
+        assert_eq!(Brightness::new(0), Brightness::MIN);
+        assert_eq!(Brightness::new(5).factor(), 0.5);
+
+        let dir = temp_dir("brightness");
+        let path = dir.join("brightness.txt");
+        assert_eq!(Brightness::load(&path), Brightness::MAX);
+
+        Brightness::new(3).save(&path).unwrap();
+        assert_eq!(Brightness::load(&path), Brightness::new(3));
+
+        fs::write(&path, "bright").unwrap();
+        assert_eq!(Brightness::load(&path), Brightness::MAX);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn backlight_is_driven_through_sysfs() {
+        let dir = temp_dir("backlight");
+        assert!(Backlight::open(&dir).is_err());
+
+        fs::write(dir.join("max_brightness"), "255\n").unwrap();
+        let backlight = Backlight::open(&dir).unwrap();
+
+        backlight.set(Brightness::new(4)).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("brightness")).unwrap(), "102");
+        backlight.set(Brightness::MAX).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("brightness")).unwrap(), "255");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,843 @@
+use crate::{
+    grid::CellColor,
+    protocol::{McduKey, McduSide},
+    server::ConnectionMode,
+};
+use serde::{Deserialize, Deserializer};
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use tokio_tungstenite::tungstenite::http::Uri;
+
+const DEFAULT_CONFIG_PATH: &str = "mcdu.toml";
+
+// The A32NX always sends 12 lines plus the title and the scratchpad, each at most 24 characters
+// long
+const MIN_SCREEN_ROWS: usize = 14;
+const MIN_SCREEN_COLS: usize = 24;
+
+/// Describes the runtime settings of the application, loaded from the configuration file and
+/// overridden by the command line arguments
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub connection: ConnectionConfig,
+    pub mcdu: McduConfig,
+    pub window: WindowConfig,
+    pub bezel: BezelConfig,
+    pub screen: ScreenConfig,
+    pub brightness: BrightnessConfig,
+    pub keypad: KeypadConfig,
+    pub keyboard: KeyboardConfig,
+    pub key_repeat: KeyRepeatConfig,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConnectionConfig {
+    /// Address the WebSocket server listens on
+    pub bind: String,
+    /// WebSocket endpoint of the MCDU server to connect to. When set, the WebSocket server is not
+    /// started
+    pub connect: Option<String>,
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            bind: "127.0.0.1:8380".to_string(),
+            connect: None,
+        }
+    }
+}
+
+impl ConnectionConfig {
+    /// Returns how the connection with the MCDU has to be established
+    pub fn mode(&self) -> ConnectionMode {
+        match &self.connect {
+            Some(url) => ConnectionMode::Client(url.clone()),
+            None => ConnectionMode::Server,
+        }
+    }
+}
+
+/// Describes how many MCDU screens are drawn in the window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenMode {
+    /// Draws the screen of the selected MCDU side only
+    #[default]
+    Single,
+    /// Draws the captain's and first officer's screens next to each other
+    Dual,
+}
+
+impl ScreenMode {
+    /// Returns the n. of screens drawn in the window
+    pub fn screen_count(self) -> usize {
+        match self {
+            ScreenMode::Single => 1,
+            ScreenMode::Dual => 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct McduConfig {
+    /// The MCDU side replicated at startup
+    pub side: McduSide,
+    /// How many MCDU screens are drawn in the window
+    pub mode: ScreenMode,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub mode: WindowMode,
+    /// Width of the window in windowed mode
+    pub width: f32,
+    /// Height of the window in windowed mode
+    pub height: f32,
+    /// Draw the line select keys and the keypad of the MCDU around the screens, to be pressed on a
+    /// touchscreen or with the mouse
+    pub touch_keys: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            mode: WindowMode::BorderlessFullscreen,
+            width: 1280.0,
+            height: 720.0,
+            touch_keys: false,
+        }
+    }
+}
+
+/// Describes how the window is shown on the monitor
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowMode {
+    Windowed,
+    BorderlessFullscreen,
+    SizedFullscreen,
+    Fullscreen,
+}
+
+#[cfg(feature = "bevy")]
+impl From<WindowMode> for bevy::window::WindowMode {
+    fn from(mode: WindowMode) -> Self {
+        match mode {
+            WindowMode::Windowed => bevy::window::WindowMode::Windowed,
+            WindowMode::BorderlessFullscreen => bevy::window::WindowMode::BorderlessFullscreen,
+            WindowMode::SizedFullscreen => bevy::window::WindowMode::SizedFullscreen,
+            WindowMode::Fullscreen => bevy::window::WindowMode::Fullscreen,
+        }
+    }
+}
+
+/// Describes where the LCD panel is visible through the bezel of the MCDU housing, and how it is
+/// mounted
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BezelConfig {
+    /// Part of the window hidden by the top side of the bezel
+    pub top: Length,
+    /// Part of the window hidden by the bottom side of the bezel
+    pub bottom: Length,
+    /// Part of the window hidden by the left side of the bezel
+    pub left: Length,
+    /// Part of the window hidden by the right side of the bezel
+    pub right: Length,
+    /// Clockwise rotation of the screens, for panels mounted in portrait
+    pub rotation: Rotation,
+    /// Mirrors the screens horizontally
+    pub flip_horizontal: bool,
+    /// Mirrors the screens vertically
+    pub flip_vertical: bool,
+}
+
+impl BezelConfig {
+    /// Computes the area the screens are laid out in, within a window of the given size. The area
+    /// is centred on the part of the window visible through the bezel and, when rotated by 90 or
+    /// 270 degrees, has its width and height swapped so that it covers that part once rotated
+    pub fn screen_area(&self, window_width: f32, window_height: f32) -> ScreenArea {
+        let left = self.left.resolve(window_width);
+        let top = self.top.resolve(window_height);
+        let visible_width = (window_width - left - self.right.resolve(window_width)).max(0.0);
+        let visible_height = (window_height - top - self.bottom.resolve(window_height)).max(0.0);
+
+        let (width, height) = match self.rotation {
+            Rotation::None | Rotation::Half => (visible_width, visible_height),
+            Rotation::Quarter | Rotation::ThreeQuarters => (visible_height, visible_width),
+        };
+
+        ScreenArea {
+            left: left + (visible_width - width) / 2.0,
+            top: top + (visible_height - height) / 2.0,
+            width,
+            height,
+        }
+    }
+}
+
+/// Represents the area of the window the screens are laid out in, before being rotated around
+/// its centre
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenArea {
+    pub left: f32,
+    pub top: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Represents a length relative to the window, written either in pixels (e.g. 24 or "24px") or
+/// as a percentage of the window size (e.g. "5%") in the configuration file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Length {
+    Px(f32),
+    Percent(f32),
+}
+
+impl Default for Length {
+    fn default() -> Self {
+        Length::Px(0.0)
+    }
+}
+
+impl Length {
+    /// Converts the length to pixels, given the size of the window along the same axis
+    pub fn resolve(self, window_size: f32) -> f32 {
+        match self {
+            Length::Px(px) => px,
+            Length::Percent(percent) => window_size * percent / 100.0,
+        }
+    }
+
+    fn value(self) -> f32 {
+        match self {
+            Length::Px(value) | Length::Percent(value) => value,
+        }
+    }
+}
+
+impl FromStr for Length {
+    type Err = String;
+
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        let trimmed = str.trim();
+        let length = if let Some(percent) = trimmed.strip_suffix('%') {
+            percent.trim().parse().map(Length::Percent)
+        } else {
+            trimmed
+                .trim_end_matches("px")
+                .trim()
+                .parse()
+                .map(Length::Px)
+        };
+
+        length.map_err(|_| {
+            format!(
+                "invalid length \"{}\", expected e.g. \"24px\" or \"5%\"",
+                str
+            )
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Length {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawLength {
+            Px(f32),
+            Str(String),
+        }
+
+        match RawLength::deserialize(deserializer)? {
+            RawLength::Px(px) => Ok(Length::Px(px)),
+            RawLength::Str(str) => str.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Represents the clockwise rotations the screens can be drawn with, written in degrees (0, 90,
+/// 180 or 270) in the configuration file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "u16")]
+pub enum Rotation {
+    #[default]
+    None,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+impl Rotation {
+    /// Returns the clockwise angle of the rotation in degrees
+    pub fn degrees(self) -> f32 {
+        match self {
+            Rotation::None => 0.0,
+            Rotation::Quarter => 90.0,
+            Rotation::Half => 180.0,
+            Rotation::ThreeQuarters => 270.0,
+        }
+    }
+}
+
+impl TryFrom<u16> for Rotation {
+    type Error = String;
+
+    fn try_from(degrees: u16) -> Result<Self, Self::Error> {
+        match degrees {
+            0 => Ok(Rotation::None),
+            90 => Ok(Rotation::Quarter),
+            180 => Ok(Rotation::Half),
+            270 => Ok(Rotation::ThreeQuarters),
+            _ => Err(format!(
+                "invalid rotation {}, expected 0, 90, 180 or 270 degrees",
+                degrees
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScreenConfig {
+    /// Height of the MCDU screen in characters
+    pub rows: usize,
+    /// Width of the MCDU screen in characters
+    pub cols: usize,
+    /// Ratio between the height and the width of a single character
+    pub font_aspect_ratio: f32,
+    /// Height of the font relative to the height of a row
+    pub font_size_percent: f32,
+    /// Font used for big characters, relative to the assets folder
+    pub font: String,
+    /// Font used for small characters, relative to the assets folder
+    pub font_small: String,
+    pub background: HexColor,
+    pub colors: ColorPalette,
+}
+
+impl Default for ScreenConfig {
+    fn default() -> Self {
+        Self {
+            rows: 14,
+            cols: 25,
+            font_aspect_ratio: 1.3850,
+            font_size_percent: 0.90,
+            font: "HoneywellMCDU.ttf".to_string(),
+            font_small: "HoneywellMCDUSmall.ttf".to_string(),
+            background: HexColor::new(0x0d, 0x14, 0x24),
+            colors: ColorPalette::default(),
+        }
+    }
+}
+
+impl ScreenConfig {
+    /// Computes the height of a single row based on the font size
+    pub fn row_height(&self, font_size: f32) -> f32 {
+        font_size / self.font_size_percent
+    }
+
+    /// Computes the width of a single character cell based on the font size
+    pub fn cell_width(&self, font_size: f32) -> f32 {
+        font_size / self.font_aspect_ratio
+    }
+
+    /// Computes the horizontal whitespace between the end of a grapheme and the start of the next
+    /// one
+    pub fn font_whitespace(&self, font_size: f32) -> f32 {
+        font_size - self.cell_width(font_size)
+    }
+
+    /// Computes the width of a single row based on the font size and the n. of columns we have to
+    /// display
+    pub fn row_width(&self, font_size: f32) -> f32 {
+        self.cell_width(font_size) * (self.cols as f32)
+    }
+
+    /// Computes the thickness of the outline drawn around boxed text based on the font size, never
+    /// thinner than a pixel
+    pub fn box_border(&self, font_size: f32) -> f32 {
+        (font_size / 16.0).round().max(1.0)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BrightnessConfig {
+    /// File the brightness set with the BRT and DIM keys is saved to, restored on the next start
+    pub file: PathBuf,
+    /// Directory of the Linux backlight device driven by the BRT and DIM keys (e.g.
+    /// "/sys/class/backlight/rpi_backlight"). Without one, the colors of the screen are dimmed
+    /// instead
+    pub backlight: Option<PathBuf>,
+}
+
+impl Default for BrightnessConfig {
+    fn default() -> Self {
+        Self {
+            file: PathBuf::from("brightness.txt"),
+            backlight: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeypadConfig {
+    /// Serial device of the microcontroller scanning the keypad (e.g. "/dev/ttyACM0"). Without
+    /// one, no physical keypad is read
+    pub serial_device: Option<String>,
+    /// Baud rate of the serial link with the microcontroller
+    pub baud_rate: u32,
+    /// Input device of a USB keypad read through evdev (e.g. "/dev/input/event3"). Without one, no
+    /// USB keypad is read
+    pub evdev_device: Option<String>,
+    /// Whether the USB keypad is grabbed, so that its keys do not reach other applications
+    pub grab: bool,
+    /// Scancodes of the USB keypad mapped to the keys of the MCDU's keypad, in addition to the keys
+    /// of a standard keyboard
+    pub scancodes: HashMap<String, McduKey>,
+}
+
+impl Default for KeypadConfig {
+    fn default() -> Self {
+        Self {
+            serial_device: None,
+            baud_rate: 115_200,
+            evdev_device: None,
+            grab: true,
+            scancodes: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyboardConfig {
+    /// Whether the keys of the PC keyboard press the keys of the MCDU's keypad
+    pub enabled: bool,
+    /// Keys of the PC keyboard mapped to the keys of the MCDU's keypad, on top of the default
+    /// mapping. Keys are named after their Bevy key code (e.g. "F1", "Key1" or "Back")
+    pub keys: HashMap<String, McduKey>,
+}
+
+impl Default for KeyboardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            keys: HashMap::new(),
+        }
+    }
+}
+
+/// Parses the name of a key of the PC keyboard into its Bevy key code (e.g. "Back")
+#[cfg(feature = "bevy")]
+pub fn parse_key_code(name: &str) -> Option<bevy::input::keyboard::KeyCode> {
+    use serde::de::{
+        value::{Error, StrDeserializer},
+        IntoDeserializer,
+    };
+
+    let deserializer: StrDeserializer<Error> = name.into_deserializer();
+    bevy::input::keyboard::KeyCode::deserialize(deserializer).ok()
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyRepeatConfig {
+    /// Time in milliseconds a key is held before it starts repeating
+    pub delay_ms: u64,
+    /// N. of times per second a held key repeats, 0 to disable repeats
+    pub rate: f32,
+    /// Keys repeating while held, like the slew keys of the real MCDU
+    pub keys: Vec<McduKey>,
+    /// Time in milliseconds a key is held before it is long pressed (e.g. CLR clearing the whole
+    /// scratchpad)
+    pub long_press_ms: u64,
+}
+
+impl Default for KeyRepeatConfig {
+    fn default() -> Self {
+        Self {
+            delay_ms: 500,
+            rate: 10.0,
+            keys: vec![
+                McduKey::PrevPage,
+                McduKey::NextPage,
+                McduKey::Up,
+                McduKey::Down,
+                McduKey::Brt,
+                McduKey::Dim,
+            ],
+            long_press_ms: 1000,
+        }
+    }
+}
+
+/// Describes the colors used to draw text on the MCDU screen
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorPalette {
+    pub amber: HexColor,
+    pub cyan: HexColor,
+    pub green: HexColor,
+    pub inop: HexColor,
+    pub magenta: HexColor,
+    pub red: HexColor,
+    pub white: HexColor,
+    pub yellow: HexColor,
+}
+
+impl Default for ColorPalette {
+    fn default() -> Self {
+        Self {
+            amber: HexColor::new(0xff, 0x9a, 0x00),
+            cyan: HexColor::new(0x00, 0xff, 0xff),
+            green: HexColor::new(0x00, 0xff, 0x00),
+            inop: HexColor::new(0x66, 0x66, 0x66),
+            magenta: HexColor::new(0xff, 0x94, 0xff),
+            red: HexColor::new(0xff, 0x00, 0x00),
+            white: HexColor::new(0xff, 0xff, 0xff),
+            yellow: HexColor::new(0xff, 0xff, 0x00),
+        }
+    }
+}
+
+impl ColorPalette {
+    /// Returns the color used to draw the text of a cell
+    pub fn color(&self, color: CellColor) -> HexColor {
+        match color {
+            CellColor::Amber => self.amber,
+            CellColor::Cyan => self.cyan,
+            CellColor::Green => self.green,
+            CellColor::Inop => self.inop,
+            CellColor::Magenta => self.magenta,
+            CellColor::Red => self.red,
+            CellColor::White => self.white,
+            CellColor::Yellow => self.yellow,
+        }
+    }
+}
+
+/// Represents an opaque color, written as a hex string (e.g. "#ff9a00") in the configuration file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HexColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl HexColor {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Scales the color by the given brightness factor, between 0 (black) and 1 (unchanged)
+    pub fn dimmed(self, factor: f32) -> Self {
+        let scale = |channel: u8| (f32::from(channel) * factor.clamp(0.0, 1.0)).round() as u8;
+        Self::new(scale(self.r), scale(self.g), scale(self.b))
+    }
+}
+
+impl FromStr for HexColor {
+    type Err = String;
+
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        let hex = str.trim_start_matches('#');
+        let channel = |index: usize| {
+            hex.get(index..index + 2)
+                .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+        };
+
+        match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Self::new(r, g, b)),
+            _ => Err(format!("invalid hex color \"{}\"", str)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for HexColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        hex.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "bevy")]
+impl From<HexColor> for bevy::prelude::Color {
+    fn from(color: HexColor) -> Self {
+        bevy::prelude::Color::rgb_u8(color.r, color.g, color.b)
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, err) => write!(f, "cannot read {}: {}", path.display(), err),
+            ConfigError::Parse(path, err) => write!(f, "cannot parse {}: {}", path.display(), err),
+            ConfigError::Invalid(errors) => {
+                write!(f, "invalid configuration:")?;
+                errors.iter().try_for_each(|err| write!(f, "\n  - {}", err))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Loads the given configuration file or, without one, "mcdu.toml" in the working directory
+    /// if present. Falls back to the default settings when there is no file to load
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        match path {
+            Some(path) => Self::from_file(path),
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Parses the given TOML configuration file
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let toml = fs::read_to_string(path).map_err(|e| ConfigError::Read(path.into(), e))?;
+        toml::from_str(&toml).map_err(|e| ConfigError::Parse(path.into(), e))
+    }
+
+    /// Checks the settings that cannot be validated while parsing
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+
+        if self.connection.bind.parse::<SocketAddr>().is_err() {
+            errors.push(format!(
+                "connection.bind \"{}\" is not a valid address (e.g. 127.0.0.1:8380)",
+                self.connection.bind
+            ));
+        }
+        if let Some(url) = &self.connection.connect {
+            let is_websocket = url.parse::<Uri>().is_ok_and(|uri| {
+                matches!(uri.scheme_str(), Some("ws" | "wss")) && uri.host().is_some()
+            });
+            if !is_websocket {
+                errors.push(format!(
+                    "connection.connect \"{}\" is not a ws:// or wss:// URL",
+                    url
+                ));
+            }
+        }
+        if self.window.width <= 0.0 || self.window.height <= 0.0 {
+            errors.push("window.width and window.height must be positive".to_string());
+        }
+        let bezel = &self.bezel;
+        if [bezel.top, bezel.bottom, bezel.left, bezel.right]
+            .iter()
+            .any(|length| length.value() < 0.0)
+        {
+            errors.push("bezel.top, bottom, left and right cannot be negative".to_string());
+        }
+        for (name, lengths) in [
+            ("bezel.top and bezel.bottom", [bezel.top, bezel.bottom]),
+            ("bezel.left and bezel.right", [bezel.left, bezel.right]),
+        ] {
+            let percent = lengths
+                .iter()
+                .map(|length| match length {
+                    Length::Percent(percent) => *percent,
+                    Length::Px(_) => 0.0,
+                })
+                .sum::<f32>();
+            if percent >= 100.0 {
+                errors.push(format!("{} cover the whole window", name));
+            }
+        }
+        if self.window.touch_keys
+            && (bezel.rotation != Rotation::None || bezel.flip_horizontal || bezel.flip_vertical)
+        {
+            errors.push(
+                "window.touch_keys cannot be used with a rotated or flipped bezel".to_string(),
+            );
+        }
+        for code in self.keypad.scancodes.keys() {
+            if code.parse::<u16>().is_err() {
+                errors.push(format!("keypad.scancodes \"{}\" is not a scancode", code));
+            }
+        }
+        #[cfg(feature = "bevy")]
+        for name in self.keyboard.keys.keys() {
+            if parse_key_code(name).is_none() {
+                errors.push(format!("keyboard.keys \"{}\" is not a key code", name));
+            }
+        }
+        if self.key_repeat.rate < 0.0 {
+            errors.push("key_repeat.rate cannot be negative".to_string());
+        }
+        if self.screen.rows < MIN_SCREEN_ROWS {
+            errors.push(format!("screen.rows must be at least {}", MIN_SCREEN_ROWS));
+        }
+        if self.screen.cols < MIN_SCREEN_COLS {
+            errors.push(format!("screen.cols must be at least {}", MIN_SCREEN_COLS));
+        }
+        if self.screen.font_aspect_ratio <= 0.0 {
+            errors.push("screen.font_aspect_ratio must be positive".to_string());
+        }
+        if self.screen.font_size_percent <= 0.0 || self.screen.font_size_percent > 1.0 {
+            errors.push("screen.font_size_percent must be between 0 and 1".to_string());
+        }
+        if self.screen.font.is_empty() || self.screen.font_small.is_empty() {
+            errors.push("screen.font and screen.font_small cannot be empty".to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_config_is_valid() {
+        let config = Config::from_file(Path::new("mcdu.example.toml")).unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.screen.cols, ScreenConfig::default().cols);
+        assert_eq!(config.connection.mode(), ConnectionMode::Server);
+    }
+
+    #[test]
+    fn invalid_settings_are_reported() {
+        let config: Config = toml::from_str(
+            r#"
+            [connection]
+            bind = "localhost"
+            [window]
+            touch_keys = true
+            [bezel]
+            flip_vertical = true
+            [keypad.scancodes]
+            KEY_A = "A"
+            [key_repeat]
+            rate = -1
+            [screen]
+            cols = 10
+            "#,
+        )
+        .unwrap();
+
+        match config.validate() {
+            Err(ConfigError::Invalid(errors)) => assert_eq!(errors.len(), 5),
+            other => panic!("Expected validation errors, got {:?}", other),
+        }
+        assert!(toml::from_str::<Config>("[screen]\nbackground = \"#zz\"").is_err());
+        assert!(toml::from_str::<Config>("[screen]\nunknown = 1").is_err());
+        assert!(toml::from_str::<Config>("[bezel]\nrotation = 45").is_err());
+        assert!(toml::from_str::<Config>("[bezel]\ntop = \"1cm\"").is_err());
+    }
+
+    #[test]
+    fn connect_urls_must_be_websocket_urls() {
+        let validate = |url: &str| {
+            let config: Config =
+                toml::from_str(&format!("[connection]\nconnect = \"{}\"", url)).unwrap();
+            config.validate().is_ok()
+        };
+
+        assert!(validate("ws://192.168.1.10:8380"));
+        assert!(validate("wss://mcdu.example.com/a32nx"));
+        assert!(!validate("http://192.168.1.10:8380"));
+        assert!(!validate("192.168.1.10:8380"));
+        assert!(!validate("ws://"));
+    }
+
+    #[test]
+    fn keyboard_keys_are_parsed() {
+        let config: Config = toml::from_str(
+            r#"
+            [keyboard.keys]
+            F13 = "airport"
+            Return = "A"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.keyboard.keys["F13"], McduKey::Airport);
+        assert_eq!(config.keyboard.keys["Return"], McduKey::Letter('A'));
+        assert!(toml::from_str::<Config>("[keyboard.keys]\nF1 = \"L7\"").is_err());
+    }
+
+    #[cfg(feature = "bevy")]
+    #[test]
+    fn keyboard_keys_must_be_key_codes() {
+        let validate = |name: &str| {
+            let config: Config =
+                toml::from_str(&format!("[keyboard.keys]\n{} = \"CLR\"", name)).unwrap();
+            config.validate().is_ok()
+        };
+
+        assert!(validate("Back"));
+        assert!(validate("Key1"));
+        assert!(validate("F13"));
+        assert!(!validate("Backspace"));
+        assert!(!validate("1"));
+        assert_eq!(
+            parse_key_code("NumpadDecimal"),
+            Some(bevy::input::keyboard::KeyCode::NumpadDecimal)
+        );
+    }
+
+    #[test]
+    fn screen_area_excludes_the_bezel() {
+        let mut bezel: BezelConfig = toml::from_str(
+            r#"
+            top = 20
+            bottom = "10px"
+            left = "10%"
+            right = "0%"
+            "#,
+        )
+        .unwrap();
+        let area = |bezel: &BezelConfig| bezel.screen_area(1000.0, 600.0);
+
+        assert_eq!(
+            area(&bezel),
+            ScreenArea {
+                left: 100.0,
+                top: 20.0,
+                width: 900.0,
+                height: 570.0
+            }
+        );
+
+        // Rotated by a quarter, the area is centred on the visible part of the window
+        bezel.rotation = Rotation::ThreeQuarters;
+        assert_eq!(
+            area(&bezel),
+            ScreenArea {
+                left: 265.0,
+                top: -145.0,
+                width: 570.0,
+                height: 900.0
+            }
+        );
+    }
+}
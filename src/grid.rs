@@ -0,0 +1,509 @@
+use crate::protocol::{ParsedText, ScreenUpdate, TextFormatter, TextSegment};
+use std::collections::HashSet;
+
+/// Represents the colors a character can be drawn with on the MCDU screen
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum CellColor {
+    Amber,
+    Cyan,
+    Green,
+    Inop,
+    Magenta,
+    Red,
+    White,
+    Yellow,
+}
+
+/// Represents the sizes a character can be drawn with on the MCDU screen
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum CellSize {
+    Big,
+    Small,
+}
+
+/// Represents a single character cell of the MCDU screen
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Cell {
+    pub char: char,
+    pub color: CellColor,
+    pub size: CellSize,
+    /// Whether the character is drawn in the background color over a block of its own color
+    pub inverse: bool,
+    /// Whether the cell is part of a box, drawn around consecutive boxed cells
+    pub boxed: bool,
+    /// Whether the cell is periodically hidden and shown again
+    pub flashing: bool,
+}
+
+impl Cell {
+    pub const BLANK: Cell = Cell {
+        char: ' ',
+        color: CellColor::White,
+        size: CellSize::Big,
+        inverse: false,
+        boxed: false,
+        flashing: false,
+    };
+
+    /// Checks whether the cell has nothing to draw
+    pub fn is_blank(&self) -> bool {
+        self.char.is_whitespace() && !self.inverse && !self.boxed
+    }
+}
+
+/// Describes which vertical sides of a boxed cell are part of the box outline, boxes span
+/// consecutive boxed cells of the same row
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BoxEdges {
+    pub left: bool,
+    pub right: bool,
+}
+
+/// Computes the vertical sides of the cell at the given column that are part of a box outline,
+/// if the cell is boxed
+pub fn compute_box_edges(row: &[Cell], col: usize) -> Option<BoxEdges> {
+    let is_boxed = |col: usize| row.get(col).is_some_and(|cell| cell.boxed);
+
+    is_boxed(col).then(|| BoxEdges {
+        left: col == 0 || !is_boxed(col - 1),
+        right: !is_boxed(col + 1),
+    })
+}
+
+/// Represents how a piece of text is aligned within a row
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlign {
+    pub const ALL: [TextAlign; 3] = [TextAlign::Left, TextAlign::Center, TextAlign::Right];
+}
+
+/// Represents the kinds of rows making up the screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowKind {
+    /// Shows the page title and page indicator
+    Header,
+    /// Shows one of the lines of the page
+    Content,
+    /// Shows the scratchpad and vertical scroll indicator
+    Footer,
+}
+
+impl RowKind {
+    /// Computes the kind of the row at the given index of a screen with the given n. of rows
+    pub fn of_row(row_index: usize, rows: usize) -> Self {
+        if row_index == 0 {
+            RowKind::Header
+        } else if row_index == rows - 1 {
+            RowKind::Footer
+        } else {
+            RowKind::Content
+        }
+    }
+}
+
+/// Checks whether the row at the given index shows labels, drawn in small characters by default.
+/// Only the lines of the page alternate between labels and data, starting with a label, the header
+/// and the footer are drawn in big characters whatever the n. of rows
+pub fn is_label_row(kind: RowKind, row_index: usize) -> bool {
+    kind == RowKind::Content && row_index % 2 == 1
+}
+
+/// Represents the MCDU screen as a grid of character cells, resolved exactly like the real MCDU
+/// does: every row is made of the same n. of columns so text lines up across rows
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid {
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Grid {
+    /// Lays out the given screen update on a grid of the given size
+    pub fn from_update(update: &ScreenUpdate, rows: usize, cols: usize) -> Self {
+        let rows = (0..rows)
+            .map(|row_index| {
+                let kind = RowKind::of_row(row_index, rows);
+                let parts = compute_row_parts(kind, row_index, update);
+                layout_row(&parts, is_label_row(kind, row_index), cols)
+            })
+            .collect();
+
+        Self { rows }
+    }
+
+    /// Returns the cells of the row at the given index
+    pub fn row(&self, row_index: usize) -> &[Cell] {
+        &self.rows[row_index]
+    }
+
+    /// Returns the cells of every row, from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.rows.iter().map(|row| row.as_slice())
+    }
+
+    /// Computes the positions (row and column) of the cells to redraw to go from the given grid,
+    /// last drawn, to this one: the cells whose content or box outline changed. Every cell is
+    /// redrawn when nothing was drawn yet
+    pub fn changed_cells(&self, last: Option<&Grid>) -> HashSet<(usize, usize)> {
+        let mut changed_cells = HashSet::new();
+
+        for (row_index, cells) in self.rows.iter().enumerate() {
+            let last_cells = last
+                .and_then(|last| last.rows.get(row_index))
+                .filter(|last_cells| last_cells.len() == cells.len());
+
+            for col in 0..cells.len() {
+                let is_unchanged = last_cells.is_some_and(|last_cells| {
+                    last_cells[col] == cells[col]
+                        && compute_box_edges(last_cells, col) == compute_box_edges(cells, col)
+                });
+                if !is_unchanged {
+                    changed_cells.insert((row_index, col));
+                }
+            }
+        }
+
+        changed_cells
+    }
+
+    /// Computes the grid as shown at the given phase of flashing text, flashing cells being blank
+    /// while hidden
+    pub fn at_flash_phase(&self, visible: bool) -> Grid {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| {
+                        if cell.flashing && !visible {
+                            Cell::BLANK
+                        } else {
+                            *cell
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self { rows }
+    }
+}
+
+/// Computes the parts of the screen update shown in the given row (e.g. the title and the page
+/// indicator in the header), along with their default alignment
+pub fn compute_row_parts(
+    kind: RowKind,
+    row_index: usize,
+    update: &ScreenUpdate,
+) -> Vec<(ParsedText, TextAlign)> {
+    // Missing arrows are hidden, updates built by hand may not have all of them
+    let arrow = |index: usize| update.arrows.get(index).copied().unwrap_or(false);
+
+    match kind {
+        RowKind::Header => {
+            // Render horizontal arrows when there is no page indicator
+            let page = if !update.page.is_empty() {
+                update.page.clone()
+            } else {
+                compute_arrows_text([(arrow(2), "←"), (arrow(3), "→")])
+            };
+
+            vec![
+                (update.title.clone(), TextAlign::Center),
+                (update.title_left.clone(), TextAlign::Left),
+                (page, TextAlign::Right),
+            ]
+        }
+        RowKind::Content => {
+            // Rows beyond the lines sent by the MCDU are left blank
+            let line = update.lines.get(row_index - 1);
+            TextAlign::ALL
+                .iter()
+                .enumerate()
+                .map(|(col_index, align)| {
+                    let column = line.and_then(|line| line.get(col_index));
+                    (column.cloned().unwrap_or_default(), *align)
+                })
+                .collect()
+        }
+        RowKind::Footer => vec![
+            (update.scratchpad.clone(), TextAlign::Left),
+            (
+                compute_arrows_text([(arrow(1), "↓"), (arrow(0), "↑")]),
+                TextAlign::Right,
+            ),
+        ],
+    }
+}
+
+/// Computes the text used to draw a scroll indicator, made of the arrows that are visible
+fn compute_arrows_text(arrows: [(bool, &str); 2]) -> ParsedText {
+    arrows
+        .iter()
+        .map(|(is_visible, arrow)| TextSegment {
+            formatters: Vec::new(),
+            value: (if *is_visible { arrow } else { "" }).to_string(),
+        })
+        .collect()
+}
+
+/// Resolves the parts of a row into cells. Parts are drawn in order, each one on top of the
+/// previous ones, and blank characters never cover the characters drawn below them
+pub fn layout_row(parts: &[(ParsedText, TextAlign)], is_label_row: bool, cols: usize) -> Vec<Cell> {
+    let mut row = vec![Cell::BLANK; cols];
+
+    for (parsed_text, default_alignment) in parts {
+        let aligned_cells = compute_cells(parsed_text, *default_alignment, is_label_row);
+
+        for align in TextAlign::ALL {
+            let cells = aligned_cells
+                .iter()
+                .filter(|(cell_align, _)| *cell_align == align)
+                .map(|(_, cell)| *cell)
+                .collect::<Vec<_>>();
+
+            // Compute the column of the first character, text overflowing the row is cut
+            let len = cells.len() as isize;
+            let start = match align {
+                TextAlign::Left => 0,
+                TextAlign::Center => (cols as isize - len).div_euclid(2),
+                TextAlign::Right => cols as isize - len,
+            };
+
+            for (offset, cell) in cells.into_iter().enumerate() {
+                let col = start + offset as isize;
+                if col >= 0 && (col as usize) < cols && !cell.is_blank() {
+                    row[col as usize] = cell;
+                }
+            }
+        }
+    }
+
+    row
+}
+
+/// Computes the cells that make up the given parsed text, along with their alignment
+fn compute_cells(
+    parsed_text: &ParsedText,
+    default_alignment: TextAlign,
+    is_label_row: bool,
+) -> Vec<(TextAlign, Cell)> {
+    let mut cells = Vec::new();
+
+    for TextSegment { formatters, value } in parsed_text {
+        let mut size = if is_label_row {
+            CellSize::Small
+        } else {
+            CellSize::Big
+        };
+        let mut color = CellColor::White;
+        let mut align = default_alignment;
+        let mut inverse = false;
+        let mut boxed = false;
+        let mut flashing = false;
+
+        for formatter in formatters {
+            match formatter {
+                TextFormatter::AlignLeft => align = TextAlign::Left,
+                TextFormatter::AlignRight => align = TextAlign::Right,
+                TextFormatter::ColorAmber => color = CellColor::Amber,
+                TextFormatter::ColorCyan => color = CellColor::Cyan,
+                TextFormatter::ColorGreen => color = CellColor::Green,
+                TextFormatter::ColorInop => color = CellColor::Inop,
+                TextFormatter::ColorMagenta => color = CellColor::Magenta,
+                TextFormatter::ColorRed => color = CellColor::Red,
+                TextFormatter::ColorWhite => color = CellColor::White,
+                TextFormatter::ColorYellow => color = CellColor::Yellow,
+                TextFormatter::FontBig => size = CellSize::Big,
+                TextFormatter::FontSmall => size = CellSize::Small,
+                TextFormatter::Inverse => inverse = true,
+                TextFormatter::Boxed => boxed = true,
+                TextFormatter::Flash => flashing = true,
+            }
+        }
+
+        cells.extend(value.chars().map(|char| {
+            let cell = Cell {
+                char,
+                color,
+                size,
+                inverse,
+                boxed,
+                flashing,
+            };
+            (align, cell)
+        }));
+    }
+
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{LINE_COLUMNS, SCREEN_ARROWS, SCREEN_LINES};
+
+    fn segment(formatters: Vec<TextFormatter>, value: &str) -> TextSegment {
+        TextSegment {
+            formatters,
+            value: value.to_string(),
+        }
+    }
+
+    fn text(row: &[Cell]) -> String {
+        row.iter().map(|cell| cell.char).collect()
+    }
+
+    #[test]
+    fn columns_are_aligned_within_the_row() {
+        let parts = vec![
+            (vec![segment(vec![], "LEFT")], TextAlign::Left),
+            (vec![segment(vec![], "MID")], TextAlign::Center),
+            (vec![segment(vec![], "RIGHT")], TextAlign::Right),
+        ];
+        let row = layout_row(&parts, false, 24);
+
+        assert_eq!(text(&row), "LEFT      MID      RIGHT");
+    }
+
+    #[test]
+    fn overlapping_text_is_resolved_in_drawing_order() {
+        let parts = vec![
+            (vec![segment(vec![], "AAAAAA")], TextAlign::Left),
+            (vec![segment(vec![], "  BB")], TextAlign::Left),
+            (
+                vec![segment(vec![TextFormatter::ColorAmber], "CCC")],
+                TextAlign::Right,
+            ),
+        ];
+        let row = layout_row(&parts, false, 8);
+
+        assert_eq!(text(&row), "AABBACCC");
+        assert_eq!(row[0].color, CellColor::White);
+        assert_eq!(row[7].color, CellColor::Amber);
+    }
+
+    #[test]
+    fn formatters_set_size_and_override_alignment() {
+        let parts = vec![(
+            vec![
+                segment(vec![TextFormatter::FontBig], "B"),
+                segment(vec![TextFormatter::AlignRight], "S"),
+            ],
+            TextAlign::Left,
+        )];
+        let row = layout_row(&parts, true, 4);
+
+        assert_eq!(text(&row), "B  S");
+        assert_eq!(row[0].size, CellSize::Big);
+        assert_eq!(row[3].size, CellSize::Small);
+    }
+
+    #[test]
+    fn box_edges_span_consecutive_boxed_cells() {
+        let parts = vec![(
+            vec![
+                segment(vec![TextFormatter::Boxed], "  "),
+                segment(vec![], "A"),
+                segment(vec![TextFormatter::Boxed, TextFormatter::Inverse], "B"),
+            ],
+            TextAlign::Left,
+        )];
+        let row = layout_row(&parts, false, 5);
+
+        let edges = |left, right| Some(BoxEdges { left, right });
+        assert_eq!(compute_box_edges(&row, 0), edges(true, false));
+        assert_eq!(compute_box_edges(&row, 1), edges(false, true));
+        assert_eq!(compute_box_edges(&row, 2), None);
+        assert_eq!(compute_box_edges(&row, 3), edges(true, true));
+        assert!(!row[0].is_blank() && row[4].is_blank());
+    }
+
+    #[test]
+    fn flashing_cells_are_hidden_out_of_phase() {
+        let update = ScreenUpdate {
+            scratchpad: vec![
+                segment(vec![], "A"),
+                segment(vec![TextFormatter::Flash, TextFormatter::Inverse], "B"),
+            ],
+            ..ScreenUpdate::default()
+        };
+        let grid = Grid::from_update(&update, 14, 4);
+
+        assert_eq!(grid.at_flash_phase(true), grid);
+        let hidden = grid.at_flash_phase(false);
+        assert_eq!(text(hidden.row(13)), "A   ");
+        assert!(hidden.row(13)[1].is_blank());
+    }
+
+    #[test]
+    fn only_the_changed_cells_are_redrawn() {
+        let mut update = ScreenUpdate::default();
+        update.lines[1][0] = vec![segment(vec![], "DATA")];
+        let grid = Grid::from_update(&update, 14, 8);
+
+        assert_eq!(grid.changed_cells(None).len(), 14 * 8);
+        let same_grid = Grid::from_update(&update, 14, 8);
+        assert!(same_grid.changed_cells(Some(&grid)).is_empty());
+
+        update.scratchpad = vec![segment(vec![TextFormatter::Boxed], "AB")];
+        let changed_grid = Grid::from_update(&update, 14, 8);
+        assert_eq!(
+            changed_grid.changed_cells(Some(&grid)),
+            HashSet::from([(13, 0), (13, 1)])
+        );
+    }
+
+    #[test]
+    fn missing_arrows_are_hidden() {
+        let update = ScreenUpdate {
+            lines: Vec::new(),
+            arrows: vec![true],
+            ..ScreenUpdate::default()
+        };
+        let grid = Grid::from_update(&update, 14, 4);
+
+        assert_eq!(text(grid.row(0)), "    ");
+        assert_eq!(text(grid.row(13)), "   ↑");
+    }
+
+    #[test]
+    fn only_the_lines_of_the_page_alternate_labels_and_data() {
+        let mut update = ScreenUpdate {
+            lines: vec![vec![Vec::new(); LINE_COLUMNS]; SCREEN_LINES],
+            scratchpad: vec![segment(vec![], "SCRATCH")],
+            title: vec![segment(vec![], "TITLE")],
+            title_left: Vec::new(),
+            page: Vec::new(),
+            arrows: vec![false; SCREEN_ARROWS],
+        };
+        update.lines[0][0] = vec![segment(vec![], "LABEL")];
+        update.lines[1][0] = vec![segment(vec![], "DATA")];
+
+        for rows in [14, 15] {
+            let grid = Grid::from_update(&update, rows, 24);
+            assert_eq!(
+                grid.row(0)[9].size,
+                CellSize::Big,
+                "header of {} rows",
+                rows
+            );
+            assert_eq!(
+                grid.row(1)[0].size,
+                CellSize::Small,
+                "label of {} rows",
+                rows
+            );
+            assert_eq!(grid.row(2)[0].size, CellSize::Big, "data of {} rows", rows);
+            assert_eq!(
+                grid.row(rows - 1)[0].size,
+                CellSize::Big,
+                "footer of {} rows",
+                rows
+            );
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{
+    config::{Config, HexColor, ScreenConfig},
+    grid::{compute_box_edges, CellSize, Grid},
+    parser::parse_update,
+    protocol::{McduSide, ScreenUpdate},
+};
+use ab_glyph::{point, Font, FontRef, ScaleFont};
+use image::{ImageError, Rgba, RgbaImage};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Height in pixels of the characters drawn by the headless renderer
+pub const RENDER_FONT_SIZE: f32 = 32.0;
+
+// The headless renderer always uses the bundled fonts, so that images do not depend on the assets
+// folder of the machine rendering them
+const FONT_BIG: &[u8] = include_bytes!("../assets/HoneywellMCDU.ttf");
+const FONT_SMALL: &[u8] = include_bytes!("../assets/HoneywellMCDUSmall.ttf");
+
+#[derive(Debug)]
+pub enum RenderError {
+    /// The file containing the screen update could not be read
+    Read(PathBuf, io::Error),
+    /// The file does not contain a valid screen update
+    Parse(serde_json::Error),
+    /// The image could not be written to the given file
+    Write(PathBuf, ImageError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Read(path, err) => write!(f, "cannot read {}: {}", path.display(), err),
+            RenderError::Parse(err) => write!(f, "invalid update data: {}", err),
+            RenderError::Write(path, err) => write!(f, "cannot write {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// Renders the screen update contained in the given file (either the raw JSON or the whole
+/// "update:" message sent by the MCDU) to a PNG image, showing the configured MCDU side
+pub fn render_file(
+    update_path: &Path,
+    image_path: &Path,
+    config: &Config,
+) -> Result<(), RenderError> {
+    let json = fs::read_to_string(update_path)
+        .map_err(|err| RenderError::Read(update_path.into(), err))?;
+    let image = render_json(&json, config.mcdu.side, RENDER_FONT_SIZE, &config.screen)?;
+
+    image
+        .save(image_path)
+        .map_err(|err| RenderError::Write(image_path.into(), err))
+}
+
+/// Renders the screen of the given MCDU side from the JSON of an update, optionally prefixed by
+/// the "update:" command
+pub fn render_json(
+    json: &str,
+    side: McduSide,
+    font_size: f32,
+    cfg: &ScreenConfig,
+) -> Result<RgbaImage, RenderError> {
+    let data = json.trim().trim_start_matches("update:");
+    let update = parse_update(data).map_err(RenderError::Parse)?;
+
+    Ok(render_update(update.side(side), font_size, cfg))
+}
+
+/// Renders a screen update to an image without a window or GPU, using the same layout as the
+/// screen plugin with characters `font_size` pixels tall. Flashing text is drawn as when visible
+pub fn render_update(update: &ScreenUpdate, font_size: f32, cfg: &ScreenConfig) -> RgbaImage {
+    let font_big = FontRef::try_from_slice(FONT_BIG).unwrap();
+    let font_small = FontRef::try_from_slice(FONT_SMALL).unwrap();
+
+    let font_whitespace = cfg.font_whitespace(font_size);
+    let row_height = cfg.row_height(font_size);
+    let cell_width = cfg.cell_width(font_size);
+    let width = (cfg.row_width(font_size) + font_whitespace).ceil() as u32;
+    let height = (row_height * cfg.rows as f32).ceil() as u32;
+
+    let background = color_to_rgba(cfg.background);
+    let mut image = RgbaImage::from_pixel(width, height, background);
+
+    let box_border = cfg.box_border(font_size);
+
+    let grid = Grid::from_update(update, cfg.rows, cfg.cols);
+    for row_index in 0..cfg.rows {
+        let row = grid.row(row_index);
+        for (col, cell) in row.iter().enumerate() {
+            if cell.is_blank() {
+                continue;
+            }
+
+            // Inverse cells are filled with the text color, boxes are outlined with it
+            let left = font_whitespace + (col as f32) * cell_width;
+            let top = (row_index as f32) * row_height;
+            let (right, bottom) = (left + cell_width, top + row_height);
+            let cell_color = color_to_rgba(cfg.colors.color(cell.color));
+            if cell.inverse {
+                fill_rect(&mut image, (left, top, right, bottom), cell_color);
+            }
+            if let Some(edges) = compute_box_edges(row, col) {
+                let (inner_top, inner_bottom) = (top + box_border, bottom - box_border);
+                fill_rect(&mut image, (left, top, right, inner_top), cell_color);
+                fill_rect(&mut image, (left, inner_bottom, right, bottom), cell_color);
+                if edges.left {
+                    fill_rect(
+                        &mut image,
+                        (left, top, left + box_border, bottom),
+                        cell_color,
+                    );
+                }
+                if edges.right {
+                    fill_rect(
+                        &mut image,
+                        (right - box_border, top, right, bottom),
+                        cell_color,
+                    );
+                }
+            }
+
+            let font = match cell.size {
+                CellSize::Big => &font_big,
+                CellSize::Small => &font_small,
+            };
+            let scaled_font = font.as_scaled(font_size);
+
+            // Glyphs are drawn from the top of their cell, like text elements in the screen plugin
+            let y = top + scaled_font.ascent();
+            let glyph = scaled_font
+                .glyph_id(cell.char)
+                .with_scale_and_position(font_size, point(left, y));
+            let outline = match font.outline_glyph(glyph) {
+                Some(outline) => outline,
+                None => continue,
+            };
+
+            let color = if cell.inverse { background } else { cell_color };
+            let bounds = outline.px_bounds();
+            outline.draw(|glyph_x, glyph_y, coverage| {
+                let pixel_x = bounds.min.x as i64 + glyph_x as i64;
+                let pixel_y = bounds.min.y as i64 + glyph_y as i64;
+                if pixel_x < 0 || pixel_y < 0 || pixel_x >= width as i64 || pixel_y >= height as i64
+                {
+                    return;
+                }
+
+                let pixel = image.get_pixel_mut(pixel_x as u32, pixel_y as u32);
+                *pixel = blend(*pixel, color, coverage);
+            });
+        }
+    }
+
+    image
+}
+
+/// Converts a color to an opaque pixel
+fn color_to_rgba(color: HexColor) -> Rgba<u8> {
+    Rgba([color.r, color.g, color.b, 0xff])
+}
+
+/// Fills the pixels within the given left, top, right and bottom edges, rounded to the nearest
+/// pixel so that the rectangles of adjacent cells do not overlap
+fn fill_rect(
+    image: &mut RgbaImage,
+    (left, top, right, bottom): (f32, f32, f32, f32),
+    color: Rgba<u8>,
+) {
+    let (width, height) = image.dimensions();
+    let clamp = |value: f32, max: u32| (value.round().max(0.0) as u32).min(max);
+
+    for y in clamp(top, height)..clamp(bottom, height) {
+        for x in clamp(left, width)..clamp(right, width) {
+            image.put_pixel(x, y, color);
+        }
+    }
+}
+
+/// Blends the given color on top of a pixel, given the coverage of the glyph being drawn
+fn blend(pixel: Rgba<u8>, color: Rgba<u8>, coverage: f32) -> Rgba<u8> {
+    let coverage = coverage.clamp(0.0, 1.0);
+    let mut blended = pixel;
+    for channel in 0..3 {
+        let value = pixel[channel] as f32 * (1.0 - coverage) + color[channel] as f32 * coverage;
+        blended[channel] = value.round() as u8;
+    }
+
+    blended
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// Compares the image against the golden PNG with the given name, stored in `tests/snapshots`.
+    /// Goldens are only written when `UPDATE_SNAPSHOTS` is set, a missing golden fails the test
+    fn assert_snapshot(name: &str, image: &RgbaImage) {
+        let snapshots_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots");
+        let golden_path = snapshots_dir.join(format!("{}.png", name));
+
+        if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            fs::create_dir_all(&snapshots_dir).unwrap();
+            image.save(&golden_path).unwrap();
+            return;
+        }
+        assert!(
+            golden_path.exists(),
+            "{} has no golden image at {}. Run the tests with UPDATE_SNAPSHOTS=1 to create it",
+            name,
+            golden_path.display()
+        );
+
+        let golden = image::open(&golden_path).unwrap().to_rgba8();
+        if golden != *image {
+            let actual_path = snapshots_dir.join(format!("{}.actual.png", name));
+            image.save(&actual_path).unwrap();
+            panic!(
+                "{} does not match its golden image, see {}. Run the tests with UPDATE_SNAPSHOTS=1 \
+                 to accept the changes",
+                name,
+                actual_path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_message_matches_golden() {
+        let json = fs::read_to_string("test_message.json").unwrap();
+        let cfg = ScreenConfig::default();
+
+        // Both sides of the test message are the same, a single golden covers them
+        let image = render_json(&json, McduSide::Left, RENDER_FONT_SIZE, &cfg).unwrap();
+        assert_snapshot("test_message_left", &image);
+    }
+
+    #[test]
+    fn inverse_and_boxed_cells_are_filled_and_outlined() {
+        let json = r#"update:{"left":{"scratchpad":"{inverse}A{end}{amber}{box}  {end}{end}"},"right":{}}"#;
+        let cfg = ScreenConfig::default();
+        let image = render_json(json, McduSide::Left, RENDER_FONT_SIZE, &cfg).unwrap();
+
+        let cell_width = cfg.cell_width(RENDER_FONT_SIZE);
+        let pixel = |col: usize, x_offset: f32, y_offset: f32| {
+            let x = cfg.font_whitespace(RENDER_FONT_SIZE) + col as f32 * cell_width + x_offset;
+            let y = (cfg.rows - 1) as f32 * cfg.row_height(RENDER_FONT_SIZE) + y_offset;
+            *image.get_pixel(x as u32, y as u32)
+        };
+        let white = color_to_rgba(cfg.colors.white);
+        let amber = color_to_rgba(cfg.colors.amber);
+        let background = color_to_rgba(cfg.background);
+
+        // The inverse cell is filled, the box is outlined around both of its cells
+        assert_eq!(pixel(0, 1.0, 1.0), white);
+        assert_eq!(pixel(1, 1.0, cell_width), amber);
+        assert_eq!(pixel(1, cell_width / 2.0, 1.0), amber);
+        assert_eq!(pixel(1, cell_width, cell_width), background);
+        assert_eq!(pixel(2, cell_width - 1.0, cell_width), amber);
+        assert_eq!(pixel(3, cell_width / 2.0, 1.0), background);
+    }
+
+    #[test]
+    fn blank_update_renders_background_only() {
+        let json = r#"update:{"left":{},"right":{}}"#;
+        let cfg = ScreenConfig::default();
+        let image = render_json(json, McduSide::Left, RENDER_FONT_SIZE, &cfg).unwrap();
+
+        let background = color_to_rgba(cfg.background);
+        assert!(image.pixels().all(|pixel| *pixel == background));
+        assert!(matches!(
+            render_json("update:", McduSide::Left, RENDER_FONT_SIZE, &cfg),
+            Err(RenderError::Parse(_))
+        ));
+    }
+}
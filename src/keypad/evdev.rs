@@ -0,0 +1,261 @@
+use super::{spawn_reader, KeyState, KeypadEvent, KeypadReceiver, KeypadSender};
+use crate::{
+    config::KeypadConfig,
+    protocol::{McduKey, LEFT_LINE_SELECT_KEYS, RIGHT_LINE_SELECT_KEYS},
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read},
+    mem,
+    os::unix::io::AsRawFd,
+};
+use tracing::{debug, warn};
+
+/// Size of the timestamp starting each `struct input_event`, two `long` values
+const EVENT_TIME_SIZE: usize = 2 * mem::size_of::<libc::c_long>();
+/// Size of a `struct input_event`: the timestamp followed by the type, code and value of the event
+const EVENT_SIZE: usize = EVENT_TIME_SIZE + 8;
+/// Type of the events reporting keys going down, up or being repeated
+const EV_KEY: u16 = 0x01;
+/// ioctl grabbing the input device, `_IOW('E', 0x90, int)`
+const EVIOCGRAB: libc::c_ulong = 0x4004_4590;
+
+/// Opens the input device of the keypad on a separate thread, reopening it whenever it fails, and
+/// relays the keys mapped to the MCDU's keypad until the receiver is dropped. The device is read
+/// directly, so keys are received even when the window is not focused
+pub fn start_evdev_keypad(device: String, config: &KeypadConfig) -> KeypadReceiver {
+    let mapping = scancode_mapping(&config.scancodes);
+    let grab = config.grab;
+
+    spawn_reader(
+        device,
+        move |device| -> io::Result<File> {
+            let file = File::open(device)?;
+            if grab {
+                if let Err(err) = grab_device(&file) {
+                    warn!("Cannot grab the keypad on {}: {}", device, err);
+                }
+            }
+            Ok(file)
+        },
+        move |file, tx| read_events(file, &mapping, tx),
+    )
+}
+
+/// Grabs the input device, so that its keys are no longer delivered to other applications (e.g.
+/// typed in the console). The grab is released when the device is closed
+fn grab_device(file: &File) -> io::Result<()> {
+    // SAFETY: EVIOCGRAB takes an int by value and the file descriptor is owned by `file`
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), EVIOCGRAB as _, 1 as libc::c_int) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Relays the keys read from the given stream of input events, skipping the events that are not
+/// keys and the scancodes that are not mapped. Returns once the receiver of the events is dropped,
+/// or the first read error
+pub fn read_events(
+    mut reader: impl Read,
+    mapping: &HashMap<u16, McduKey>,
+    tx: &KeypadSender,
+) -> io::Result<()> {
+    let mut buf = [0; EVENT_SIZE];
+
+    loop {
+        reader.read_exact(&mut buf)?;
+
+        let (kind, code, value) = parse_event(&buf);
+        if kind != EV_KEY {
+            continue;
+        }
+        // Repeats are generated by the replica itself, the ones of the device are ignored
+        let state = match value {
+            0 => KeyState::Released,
+            1 => KeyState::Pressed,
+            _ => continue,
+        };
+        let key = match mapping.get(&code) {
+            Some(key) => *key,
+            None => {
+                debug!("Ignoring the unmapped scancode {}", code);
+                continue;
+            }
+        };
+
+        if tx.send(KeypadEvent { key, state }).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+/// Parses the type, code and value of a `struct input_event`, laid out in native byte order
+fn parse_event(buf: &[u8; EVENT_SIZE]) -> (u16, u16, i32) {
+    let fields = &buf[EVENT_TIME_SIZE..];
+    let kind = u16::from_ne_bytes([fields[0], fields[1]]);
+    let code = u16::from_ne_bytes([fields[2], fields[3]]);
+    let value = i32::from_ne_bytes([fields[4], fields[5], fields[6], fields[7]]);
+
+    (kind, code, value)
+}
+
+/// Builds the mapping of the scancodes of the input device to the keys of the MCDU's keypad. The
+/// keys of a standard keyboard get the same defaults as the PC keyboard, the given scancodes are
+/// added on top. Scancodes that are not numbers are skipped, validating the configuration reports
+/// them
+pub fn scancode_mapping(scancodes: &HashMap<String, McduKey>) -> HashMap<u16, McduKey> {
+    // Scancodes of the letters in the order of the QWERTY layout (e.g. KEY_Q is 16)
+    let letter_rows: [(u16, &str); 3] = [(16, "QWERTYUIOP"), (30, "ASDFGHJKL"), (44, "ZXCVBNM")];
+    let mut mapping = HashMap::new();
+
+    for (first_code, letters) in letter_rows {
+        for (code, letter) in (first_code..).zip(letters.chars()) {
+            mapping.insert(code, McduKey::Letter(letter));
+        }
+    }
+    // KEY_1 to KEY_9 are followed by KEY_0
+    for digit in 1..=9 {
+        mapping.insert(u16::from(digit) + 1, McduKey::Digit(digit));
+    }
+    mapping.insert(11, McduKey::Digit(0));
+
+    // KEY_F1 to KEY_F10, KEY_F11 and KEY_F12 come later
+    let function_codes = (59..=68).chain([87, 88]);
+    let line_select_keys = LEFT_LINE_SELECT_KEYS
+        .into_iter()
+        .chain(RIGHT_LINE_SELECT_KEYS);
+    mapping.extend(function_codes.zip(line_select_keys));
+
+    let named_keys = [
+        (1, McduKey::McduMenu),   // KEY_ESC
+        (12, McduKey::PlusMinus), // KEY_MINUS
+        (14, McduKey::Clr),       // KEY_BACKSPACE
+        (52, McduKey::Dot),       // KEY_DOT
+        (53, McduKey::Slash),     // KEY_SLASH
+        (57, McduKey::Space),     // KEY_SPACE
+        (71, McduKey::Digit(7)),  // KEY_KP7
+        (72, McduKey::Digit(8)),  // KEY_KP8
+        (73, McduKey::Digit(9)),  // KEY_KP9
+        (74, McduKey::PlusMinus), // KEY_KPMINUS
+        (75, McduKey::Digit(4)),  // KEY_KP4
+        (76, McduKey::Digit(5)),  // KEY_KP5
+        (77, McduKey::Digit(6)),  // KEY_KP6
+        (79, McduKey::Digit(1)),  // KEY_KP1
+        (80, McduKey::Digit(2)),  // KEY_KP2
+        (81, McduKey::Digit(3)),  // KEY_KP3
+        (82, McduKey::Digit(0)),  // KEY_KP0
+        (83, McduKey::Dot),       // KEY_KPDOT
+        (98, McduKey::Slash),     // KEY_KPSLASH
+        (102, McduKey::Dir),      // KEY_HOME
+        (103, McduKey::Up),       // KEY_UP
+        (104, McduKey::PrevPage), // KEY_PAGEUP
+        (107, McduKey::FPln),     // KEY_END
+        (108, McduKey::Down),     // KEY_DOWN
+        (109, McduKey::NextPage), // KEY_PAGEDOWN
+        (110, McduKey::Init),     // KEY_INSERT
+        (111, McduKey::Ovfy),     // KEY_DELETE
+    ];
+    mapping.extend(named_keys);
+
+    mapping.extend(
+        scancodes
+            .iter()
+            .filter_map(|(code, key)| Some((code.parse().ok()?, *key))),
+    );
+    mapping
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keypad::channel;
+
+    const EV_SYN: u16 = 0x00;
+    const EV_MSC: u16 = 0x04;
+
+    /// Encodes an input event as the kernel writes it to the device
+    fn input_event(kind: u16, code: u16, value: i32) -> Vec<u8> {
+        let mut event = vec![0; EVENT_TIME_SIZE];
+        event.extend(kind.to_ne_bytes());
+        event.extend(code.to_ne_bytes());
+        event.extend(value.to_ne_bytes());
+
+        event
+    }
+
+    /// Reads the keys out of the given recorded stream of input events
+    fn read_stream(events: &[Vec<u8>], mapping: &HashMap<u16, McduKey>) -> Vec<KeypadEvent> {
+        let stream = events.concat();
+        let (tx, rx) = channel();
+
+        let result = read_events(stream.as_slice(), mapping, &tx);
+        assert_eq!(
+            result.unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof,
+            "The stream ends once fully read"
+        );
+
+        rx.try_iter().collect()
+    }
+
+    #[test]
+    fn key_events_are_read_from_a_recorded_stream() {
+        let mapping = scancode_mapping(&HashMap::new());
+
+        // Pressing and holding Q, then releasing it, as recorded from a USB keyboard
+        let events = [
+            input_event(EV_MSC, 4, 0x70014),
+            input_event(EV_KEY, 16, 1),
+            input_event(EV_SYN, 0, 0),
+            input_event(EV_KEY, 16, 2),
+            input_event(EV_SYN, 0, 0),
+            input_event(EV_MSC, 4, 0x70014),
+            input_event(EV_KEY, 16, 0),
+            input_event(EV_SYN, 0, 0),
+            // KEY_MUTE is not mapped
+            input_event(EV_KEY, 113, 1),
+            input_event(EV_KEY, 14, 1),
+        ];
+
+        assert_eq!(
+            read_stream(&events, &mapping),
+            vec![
+                KeypadEvent {
+                    key: McduKey::Letter('Q'),
+                    state: KeyState::Pressed
+                },
+                KeypadEvent {
+                    key: McduKey::Letter('Q'),
+                    state: KeyState::Released
+                },
+                KeypadEvent {
+                    key: McduKey::Clr,
+                    state: KeyState::Pressed
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn scancodes_are_mapped_to_mcdu_keys() {
+        let mapping = scancode_mapping(&HashMap::new());
+        assert_eq!(mapping[&30], McduKey::Letter('A'));
+        assert_eq!(mapping[&50], McduKey::Letter('M'));
+        assert_eq!(mapping[&2], McduKey::Digit(1));
+        assert_eq!(mapping[&11], McduKey::Digit(0));
+        assert_eq!(mapping[&59], McduKey::L1);
+        assert_eq!(mapping[&68], McduKey::R4);
+        assert_eq!(mapping[&88], McduKey::R6);
+
+        let scancodes = HashMap::from([
+            ("2".to_string(), McduKey::Airport),
+            ("183".to_string(), McduKey::Brt),
+        ]);
+        let mapping = scancode_mapping(&scancodes);
+        assert_eq!(mapping[&2], McduKey::Airport);
+        assert_eq!(mapping[&183], McduKey::Brt);
+    }
+}
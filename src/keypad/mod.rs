@@ -0,0 +1,111 @@
+#[cfg(all(feature = "keypad", target_os = "linux"))]
+pub mod evdev;
+pub mod repeat;
+#[cfg(feature = "keypad")]
+pub mod serial;
+
+use crate::protocol::McduKey;
+use crossbeam_channel::{unbounded, Receiver, SendError, Sender};
+#[cfg(feature = "keypad")]
+use std::{fmt, io, thread, time::Duration};
+use std::{
+    ops::Deref,
+    sync::{Arc, Weak},
+};
+#[cfg(feature = "keypad")]
+use tracing::{info, warn};
+
+/// Time waited before opening the device of a keypad again after it failed or was unplugged
+#[cfg(feature = "keypad")]
+const REOPEN_DELAY: Duration = Duration::from_secs(1);
+
+/// Represents whether a key of a physical keypad went down or up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyState {
+    Pressed,
+    Released,
+}
+
+/// Represents a key of the MCDU's keypad being pressed or released on a physical keypad
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeypadEvent {
+    pub key: McduKey,
+    pub state: KeyState,
+}
+
+/// Creates the channel relaying the keys pressed and released on a physical keypad
+pub fn channel() -> (KeypadSender, KeypadReceiver) {
+    let (tx, rx) = unbounded();
+    let token = Arc::new(());
+    let sender = KeypadSender {
+        tx,
+        token: Arc::downgrade(&token),
+    };
+
+    (sender, KeypadReceiver { rx, _token: token })
+}
+
+/// Sends the keys pressed and released on a physical keypad to its receiver
+#[derive(Debug)]
+pub struct KeypadSender {
+    tx: Sender<KeypadEvent>,
+    token: Weak<()>,
+}
+
+impl KeypadSender {
+    /// Sends the given event, failing once the receiver is dropped
+    pub fn send(&self, event: KeypadEvent) -> Result<(), SendError<KeypadEvent>> {
+        self.tx.send(event)
+    }
+
+    /// Checks whether the receiver was dropped without sending anything, e.g. while the keypad is
+    /// idle
+    pub fn is_disconnected(&self) -> bool {
+        self.token.strong_count() == 0
+    }
+}
+
+/// Receives the keys pressed and released on a physical keypad, read until the receiver is dropped
+#[derive(Debug)]
+pub struct KeypadReceiver {
+    rx: Receiver<KeypadEvent>,
+    // Dropped along with the receiver, which the sender notices even when nothing is sent
+    _token: Arc<()>,
+}
+
+impl Deref for KeypadReceiver {
+    type Target = Receiver<KeypadEvent>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rx
+    }
+}
+
+/// Reads the device of a keypad on a separate thread, reopening it whenever it fails, until the
+/// receiver of the events is dropped. `read` relays the events of the opened device, returning
+/// once the receiver is dropped or the first error
+#[cfg(feature = "keypad")]
+fn spawn_reader<D, E: fmt::Display>(
+    device: String,
+    open: impl Fn(&str) -> Result<D, E> + Send + 'static,
+    read: impl Fn(D, &KeypadSender) -> io::Result<()> + Send + 'static,
+) -> KeypadReceiver {
+    let (tx, rx) = channel();
+
+    thread::spawn(move || loop {
+        match open(&device) {
+            Ok(opened) => {
+                info!("Reading the keypad on {}", device);
+                match read(opened, &tx) {
+                    Ok(()) => return,
+                    Err(err) => warn!("Keypad on {} disconnected: {}", device, err),
+                }
+            }
+            Err(err) => warn!("Cannot open the keypad on {}: {}", device, err),
+        }
+
+        thread::sleep(REOPEN_DELAY);
+    });
+
+    rx
+}
@@ -0,0 +1,190 @@
+use super::{KeyState, KeypadEvent};
+use crate::{config::KeyRepeatConfig, protocol::McduKey};
+use std::time::Duration;
+
+/// Keys the A32NX MCDU expects a long press for, sent once they are held long enough
+const LONG_PRESS_KEYS: [McduKey; 1] = [McduKey::Clr];
+
+/// Represents what a key of the MCDU's keypad does once its presses and releases are tracked:
+/// either pressed (when it goes down, or repeats while held) or long pressed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyAction {
+    Press(McduKey),
+    LongPress(McduKey),
+}
+
+/// Represents a key being held down
+#[derive(Debug)]
+struct HeldKey {
+    key: McduKey,
+    pressed_at: Duration,
+    next_repeat: Option<Duration>,
+    long_pressed: bool,
+}
+
+/// Tracks the keys held down on any of the keypads, turning their presses and releases into the
+/// key presses sent to the MCDU. Repeating keys are pressed again while held, and long presses
+/// are sent for the keys that expect them. Times are measured from any fixed instant, such as the
+/// start of the application
+#[derive(Debug)]
+pub struct KeyRepeater {
+    delay: Duration,
+    interval: Option<Duration>,
+    repeat_keys: Vec<McduKey>,
+    long_press: Duration,
+    held_keys: Vec<HeldKey>,
+}
+
+impl KeyRepeater {
+    pub fn new(config: &KeyRepeatConfig) -> Self {
+        Self {
+            delay: Duration::from_millis(config.delay_ms),
+            interval: (config.rate > 0.0)
+                .then(|| Duration::from_micros((1_000_000.0 / config.rate).round() as u64)),
+            repeat_keys: config.keys.clone(),
+            long_press: Duration::from_millis(config.long_press_ms),
+            held_keys: Vec::new(),
+        }
+    }
+
+    /// Handles a key going down or up at the given time. Keys are pressed when they go down, keys
+    /// already held (e.g. on another keypad) are not pressed again
+    pub fn handle(&mut self, event: KeypadEvent, now: Duration) -> Option<KeyAction> {
+        let held_index = self.held_keys.iter().position(|held| held.key == event.key);
+
+        match (event.state, held_index) {
+            (KeyState::Pressed, None) => {
+                let repeats = self.interval.is_some() && self.repeat_keys.contains(&event.key);
+                self.held_keys.push(HeldKey {
+                    key: event.key,
+                    pressed_at: now,
+                    next_repeat: repeats.then(|| now + self.delay),
+                    long_pressed: false,
+                });
+
+                Some(KeyAction::Press(event.key))
+            }
+            (KeyState::Released, Some(index)) => {
+                self.held_keys.remove(index);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Computes the actions of the keys held at the given time: the repeats that are due and the
+    /// long presses of the keys held long enough. Repeats missed while not updated (e.g. during a
+    /// slow frame) are skipped rather than sent in a burst
+    pub fn update(&mut self, now: Duration) -> Vec<KeyAction> {
+        let mut actions = Vec::new();
+
+        for held in self.held_keys.iter_mut() {
+            if let (Some(next_repeat), Some(interval)) = (held.next_repeat, self.interval) {
+                if now >= next_repeat {
+                    actions.push(KeyAction::Press(held.key));
+                    held.next_repeat = Some((next_repeat + interval).max(now + interval));
+                }
+            }
+
+            let is_long_press = now >= held.pressed_at + self.long_press;
+            if is_long_press && !held.long_pressed && LONG_PRESS_KEYS.contains(&held.key) {
+                actions.push(KeyAction::LongPress(held.key));
+                held.long_pressed = true;
+            }
+        }
+
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn press(key: McduKey) -> KeypadEvent {
+        KeypadEvent {
+            key,
+            state: KeyState::Pressed,
+        }
+    }
+
+    fn release(key: McduKey) -> KeypadEvent {
+        KeypadEvent {
+            key,
+            state: KeyState::Released,
+        }
+    }
+
+    #[test]
+    fn held_keys_repeat_after_the_delay() {
+        let mut repeater = KeyRepeater::new(&KeyRepeatConfig::default());
+
+        assert_eq!(
+            repeater.handle(press(McduKey::Up), ms(0)),
+            Some(KeyAction::Press(McduKey::Up))
+        );
+        assert_eq!(repeater.handle(press(McduKey::Up), ms(10)), None);
+        assert_eq!(repeater.update(ms(499)), vec![]);
+        assert_eq!(
+            repeater.update(ms(500)),
+            vec![KeyAction::Press(McduKey::Up)]
+        );
+        assert_eq!(repeater.update(ms(550)), vec![]);
+        assert_eq!(
+            repeater.update(ms(600)),
+            vec![KeyAction::Press(McduKey::Up)]
+        );
+
+        // A slow update sends a single repeat
+        assert_eq!(
+            repeater.update(ms(1000)),
+            vec![KeyAction::Press(McduKey::Up)]
+        );
+        assert_eq!(repeater.update(ms(1050)), vec![]);
+
+        assert_eq!(repeater.handle(release(McduKey::Up), ms(1060)), None);
+        assert_eq!(repeater.update(ms(2000)), vec![]);
+        assert_eq!(repeater.handle(release(McduKey::Up), ms(2010)), None);
+    }
+
+    #[test]
+    fn other_keys_do_not_repeat() {
+        let mut repeater = KeyRepeater::new(&KeyRepeatConfig::default());
+        repeater.handle(press(McduKey::Letter('A')), ms(0));
+        assert_eq!(repeater.update(ms(5000)), vec![]);
+
+        let config = KeyRepeatConfig {
+            rate: 0.0,
+            ..KeyRepeatConfig::default()
+        };
+        let mut repeater = KeyRepeater::new(&config);
+        repeater.handle(press(McduKey::Up), ms(0));
+        assert_eq!(repeater.update(ms(5000)), vec![]);
+    }
+
+    #[test]
+    fn clr_is_long_pressed_once() {
+        let mut repeater = KeyRepeater::new(&KeyRepeatConfig::default());
+
+        assert_eq!(
+            repeater.handle(press(McduKey::Clr), ms(0)),
+            Some(KeyAction::Press(McduKey::Clr))
+        );
+        assert_eq!(repeater.update(ms(999)), vec![]);
+        assert_eq!(
+            repeater.update(ms(1000)),
+            vec![KeyAction::LongPress(McduKey::Clr)]
+        );
+        assert_eq!(repeater.update(ms(3000)), vec![]);
+
+        // Released before the long press, CLR is pressed only once
+        repeater.handle(release(McduKey::Clr), ms(3000));
+        repeater.handle(press(McduKey::Clr), ms(4000));
+        repeater.handle(release(McduKey::Clr), ms(4500));
+        assert_eq!(repeater.update(ms(6000)), vec![]);
+    }
+}
@@ -0,0 +1,156 @@
+use super::{spawn_reader, KeyState, KeypadEvent, KeypadReceiver, KeypadSender};
+use crate::{config::KeypadConfig, protocol::McduKey};
+use std::{
+    io::{self, BufRead, BufReader},
+    time::Duration,
+};
+use tracing::warn;
+
+/// Time a read waits for data before being retried, so that unplugged devices are noticed
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Opens the serial device of the keypad on a separate thread, reopening it whenever it fails,
+/// and relays the keys reported by the microcontroller until the receiver is dropped
+pub fn start_serial_keypad(device: String, config: &KeypadConfig) -> KeypadReceiver {
+    let baud_rate = config.baud_rate;
+
+    spawn_reader(
+        device,
+        move |device| {
+            serialport::new(device, baud_rate)
+                .timeout(READ_TIMEOUT)
+                .open()
+        },
+        |port, tx| read_events(BufReader::new(port), tx),
+    )
+}
+
+/// Relays the keys read from the given stream, skipping invalid lines. Returns once the receiver
+/// of the events is dropped, or the first error that is not a read timeout
+pub fn read_events(mut reader: impl BufRead, tx: &KeypadSender) -> io::Result<()> {
+    let mut line = Vec::new();
+
+    loop {
+        // Timeouts can interrupt a line halfway, what was read so far is kept for the next try
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(_) if !line.ends_with(b"\n") => continue,
+            Ok(_) => (),
+            // Nothing is sent while the keypad is idle, so the receiver is checked on timeouts
+            Err(err) if err.kind() == io::ErrorKind::TimedOut && tx.is_disconnected() => {
+                return Ok(())
+            }
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => continue,
+            Err(err) => return Err(err),
+        }
+
+        match parse_line(&String::from_utf8_lossy(&line)) {
+            Ok(Some(event)) => {
+                if tx.send(event).is_err() {
+                    return Ok(());
+                }
+            }
+            Ok(None) => (),
+            Err(err) => warn!("Invalid keypad message: {}", err),
+        }
+        line.clear();
+    }
+}
+
+/// Parses a single line sent by the microcontroller scanning the key matrix of the MCDU. Each line
+/// reports a key going down or up, made of "D" (down) or "U" (up) followed by the name of the key
+/// as expected by the A32NX remote MCDU (e.g. "D L1" and "U CLR"). Empty lines and comments
+/// starting with "#" give nothing
+pub fn parse_line(line: &str) -> Result<Option<KeypadEvent>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let (state, key) = line
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| format!("missing key in \"{}\"", line))?;
+    let state = match state {
+        "D" | "d" => KeyState::Pressed,
+        "U" | "u" => KeyState::Released,
+        _ => return Err(format!("unknown key state \"{}\" in \"{}\"", state, line)),
+    };
+    let key = key.trim().to_ascii_uppercase().parse::<McduKey>()?;
+
+    Ok(Some(KeypadEvent { key, state }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keypad::channel;
+    use serialport::{SerialPort, TTYPort};
+    use std::io::Write;
+
+    fn event(key: McduKey, state: KeyState) -> KeypadEvent {
+        KeypadEvent { key, state }
+    }
+
+    #[test]
+    fn lines_are_parsed_into_key_events() {
+        assert_eq!(
+            parse_line("D L1\r\n"),
+            Ok(Some(event(McduKey::L1, KeyState::Pressed)))
+        );
+        assert_eq!(
+            parse_line("u plusminus"),
+            Ok(Some(event(McduKey::PlusMinus, KeyState::Released)))
+        );
+        assert_eq!(
+            parse_line("D 7"),
+            Ok(Some(event(McduKey::Digit(7), KeyState::Pressed)))
+        );
+        assert_eq!(parse_line("  "), Ok(None));
+        assert_eq!(parse_line("# keypad v1.2"), Ok(None));
+
+        assert!(parse_line("D").is_err());
+        assert!(parse_line("X L1").is_err());
+        assert!(parse_line("D L7").is_err());
+    }
+
+    /// Stands for a serial device the keypad sends nothing on
+    struct IdleDevice;
+
+    impl io::Read for IdleDevice {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::TimedOut.into())
+        }
+    }
+
+    #[test]
+    fn reading_an_idle_keypad_stops_once_the_receiver_is_dropped() {
+        let (tx, rx) = channel();
+        drop(rx);
+
+        assert!(read_events(BufReader::new(IdleDevice), &tx).is_ok());
+    }
+
+    #[test]
+    fn keys_are_read_from_a_pseudo_terminal() {
+        // The slave end is kept open by the test, otherwise writing to the master end fails
+        let (mut master, slave) = TTYPort::pair().unwrap();
+        let device = slave.name().unwrap();
+
+        let config = KeypadConfig::default();
+        let rx = start_serial_keypad(device, &config);
+
+        master.write_all(b"# hello\nD CLR\nbogus\nU C").unwrap();
+        master.write_all(b"LR\n").unwrap();
+        master.flush().unwrap();
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            rx.recv_timeout(timeout),
+            Ok(event(McduKey::Clr, KeyState::Pressed))
+        );
+        assert_eq!(
+            rx.recv_timeout(timeout),
+            Ok(event(McduKey::Clr, KeyState::Released))
+        );
+    }
+}
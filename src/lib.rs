@@ -0,0 +1,18 @@
+//! Building blocks of the A320neo's MCDU replica: the protocol spoken by FlyByWire's A32NX mod,
+//! its parser, the WebSocket server and the renderers of the MCDU screen. The renderers and the
+//! readers of physical keypads are behind the "bevy", "headless", "tui" and "keypad" features
+
+pub mod brightness;
+pub mod config;
+pub mod grid;
+#[cfg(feature = "headless")]
+pub mod headless;
+pub mod keypad;
+pub mod parser;
+#[cfg(feature = "bevy")]
+pub mod plugins;
+pub mod protocol;
+pub mod serializer;
+pub mod server;
+#[cfg(feature = "tui")]
+pub mod tui;
@@ -1,27 +1,162 @@
-mod plugins;
+use clap::Parser;
+use fbw_a32nx_mcdu::{
+    config::{Config, ConfigError, ScreenMode, WindowMode},
+    protocol::McduSide,
+};
+use std::path::{Path, PathBuf};
 
-use crate::plugins::{screen::ScreenPlugin, server::ServerPlugin};
-use bevy::{prelude::*, window::WindowMode};
-use bevy_inspector_egui::WorldInspectorPlugin;
+/// A physical replica of the A320neo's MCDU compatible with FlyByWire's A32NX mod
+#[derive(Parser)]
+#[clap(version, about)]
+struct Cli {
+    /// Path of the TOML configuration file. Defaults to "mcdu.toml" in the working directory, if
+    /// present
+    #[clap(long, value_name = "PATH")]
+    config: Option<PathBuf>,
 
-pub const BG_COLOR: Color = Color::rgb(0.05, 0.08, 0.14);
+    /// Which MCDU to replicate: "left" (captain) or "right" (first officer). Press TAB to switch
+    /// side at runtime
+    #[clap(long)]
+    side: Option<McduSide>,
 
-// Describe the height and width of the MCDU screen in characters
-pub const SCREEN_ROWS: usize = 14;
-pub const SCREEN_COLS: usize = 25;
+    /// Draw the captain's and first officer's screens next to each other. Key presses are sent
+    /// to the selected side
+    #[clap(long)]
+    dual: bool,
+
+    /// Connect to the MCDU server at the given WebSocket endpoint (e.g. ws://192.168.1.10:8380)
+    /// instead of waiting for the MCDU to connect
+    #[clap(long, value_name = "URL")]
+    connect: Option<String>,
+
+    /// Address the WebSocket server listens on for the MCDU to connect
+    #[clap(long, value_name = "ADDR")]
+    bind: Option<String>,
+
+    /// Run in a window instead of fullscreen
+    #[clap(long)]
+    windowed: bool,
+
+    /// Render the screen update contained in the given JSON file (e.g. test_message.json) to a PNG
+    /// image and exit, without opening a window. The selected side is rendered
+    #[clap(long, value_name = "UPDATE")]
+    render: Option<PathBuf>,
+
+    /// Path of the PNG image written by --render
+    #[clap(
+        long,
+        value_name = "PNG",
+        default_value = "screen.png",
+        requires = "render"
+    )]
+    output: PathBuf,
+
+    /// Draw the screen in the terminal instead of opening a window, e.g. when running over SSH
+    #[clap(long, conflicts_with = "render")]
+    tui: bool,
+}
 
 fn main() {
+    let cli = Cli::parse();
+    let config = match load_config(&cli) {
+        Ok(config) => config,
+        Err(err) => exit_with_error(err),
+    };
+
+    if let Some(update_path) = &cli.render {
+        render(update_path, &cli.output, &config);
+        return;
+    }
+
+    if cli.tui {
+        run_tui(&config);
+        return;
+    }
+
+    run_app(config);
+}
+
+/// Loads the configuration file (if any) and applies the command line overrides on top of it
+fn load_config(cli: &Cli) -> Result<Config, ConfigError> {
+    let mut config = Config::load(cli.config.as_deref())?;
+
+    if let Some(side) = cli.side {
+        config.mcdu.side = side;
+    }
+    if cli.dual {
+        config.mcdu.mode = ScreenMode::Dual;
+    }
+    if let Some(url) = &cli.connect {
+        config.connection.connect = Some(url.clone());
+    }
+    if let Some(bind) = &cli.bind {
+        config.connection.bind = bind.clone();
+    }
+    if cli.windowed {
+        config.window.mode = WindowMode::Windowed;
+    }
+
+    config.validate()?;
+    Ok(config)
+}
+
+fn exit_with_error(err: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", err);
+    std::process::exit(1);
+}
+
+/// Renders the screen update contained in the given file to a PNG image
+#[cfg(feature = "headless")]
+fn render(update_path: &Path, output_path: &Path, config: &Config) {
+    if let Err(err) = fbw_a32nx_mcdu::headless::render_file(update_path, output_path, config) {
+        exit_with_error(err);
+    }
+}
+
+#[cfg(not(feature = "headless"))]
+fn render(_update_path: &Path, _output_path: &Path, _config: &Config) {
+    exit_with_error("built without the \"headless\" feature, --render is not available");
+}
+
+/// Draws the MCDU screen in the terminal
+#[cfg(feature = "tui")]
+fn run_tui(config: &Config) {
+    if let Err(err) = fbw_a32nx_mcdu::tui::run(config) {
+        exit_with_error(err);
+    }
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_tui(_config: &Config) {
+    exit_with_error("built without the \"tui\" feature, --tui is not available");
+}
+
+/// Draws the MCDU screen in a window
+#[cfg(feature = "bevy")]
+fn run_app(config: Config) {
+    use bevy::prelude::*;
+    use bevy_inspector_egui::WorldInspectorPlugin;
+    use fbw_a32nx_mcdu::plugins::{
+        keyboard::KeyboardPlugin, keypad::KeypadPlugin, screen::ScreenPlugin, server::ServerPlugin,
+    };
+
     let mut bevy_app = App::new();
     bevy_app
-        .insert_resource(ClearColor(BG_COLOR))
+        .insert_resource(ClearColor(config.screen.background.into()))
+        .insert_resource(config.mcdu.side)
         .insert_resource(WindowDescriptor {
             title: "FlyByWire A32NX MCDU".to_string(),
-            mode: WindowMode::BorderlessFullscreen,
+            mode: config.window.mode.into(),
+            width: config.window.width,
+            height: config.window.height,
             ..default()
         })
+        .insert_resource(config)
         .add_plugins(DefaultPlugins)
         .add_plugin(ScreenPlugin)
         .add_plugin(ServerPlugin)
+        .add_plugin(KeypadPlugin)
+        .add_plugin(KeyboardPlugin)
         .add_startup_system(setup);
 
     if cfg!(feature = "debug-mode") {
@@ -31,7 +166,13 @@ fn main() {
     bevy_app.run();
 }
 
-fn setup(mut commands: Commands) {
+#[cfg(not(feature = "bevy"))]
+fn run_app(_config: Config) {
+    exit_with_error("built without the \"bevy\" feature, use --tui or --render");
+}
+
+#[cfg(feature = "bevy")]
+fn setup(mut commands: bevy::prelude::Commands) {
     // Setup 2D camera
-    commands.spawn_bundle(UiCameraBundle::default());
+    commands.spawn_bundle(bevy::prelude::UiCameraBundle::default());
 }
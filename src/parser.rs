@@ -0,0 +1,469 @@
+use crate::protocol::{
+    McduUpdate, ParsedText, ScreenUpdate, TextFormatter, TextSegment, LINE_COLUMNS, SCREEN_ARROWS,
+    SCREEN_LINES,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{Mutex, OnceLock, PoisonError},
+};
+use tracing::{debug, warn};
+
+/// Represents the message sent to the server when a screen update is requested by the client
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct ScreenUpdateMessage {
+    pub right: ScreenState,
+    pub left: ScreenState,
+}
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub(crate) struct ScreenState {
+    pub lines: Vec<Vec<String>>,
+    pub scratchpad: String,
+    pub title: String,
+    #[serde(rename = "titleLeft", alias = "title_left")]
+    pub title_left: String,
+    pub page: String,
+    pub arrows: Vec<bool>,
+}
+
+/// Parses the data of the "update" command sent by the MCDU, made of the screen state of both
+/// sides in JSON format
+pub fn parse_update(json: &str) -> Result<McduUpdate, serde_json::Error> {
+    // Replace unrenderable unicode character used as whitespace with a simple space
+    let json_msg = json.replace('\u{a0}', " ");
+
+    // Construct the screen update for both sides
+    let msg = serde_json::from_str::<ScreenUpdateMessage>(&json_msg)?;
+    Ok(McduUpdate {
+        left: build_screen_update(msg.left),
+        right: build_screen_update(msg.right),
+    })
+}
+
+/// Builds the screen update of a single MCDU from its raw state. Missing lines, columns and arrows
+/// are left blank while extra ones are dropped
+fn build_screen_update(mut raw_screen_update: ScreenState) -> ScreenUpdate {
+    raw_screen_update.lines.resize_with(SCREEN_LINES, Vec::new);
+    raw_screen_update.arrows.resize(SCREEN_ARROWS, false);
+
+    ScreenUpdate {
+        lines: raw_screen_update
+            .lines
+            .into_iter()
+            .map(|mut line| {
+                line.resize_with(LINE_COLUMNS, String::new);
+
+                // Parse the line and swap the right and center column (FlyByWire's A32NX mod
+                // uses the following layout [left, right, center] to represent a line whereas
+                // in this project I prefer to use [left, center, right])
+                let mut line = line
+                    .into_iter()
+                    .map(|raw_text| parse_raw_text(&raw_text))
+                    .collect::<Vec<ParsedText>>();
+                line.swap(1, 2);
+
+                line
+            })
+            .collect(),
+        scratchpad: parse_raw_text(&raw_screen_update.scratchpad),
+        title: parse_raw_text(&raw_screen_update.title),
+        title_left: parse_raw_text(&raw_screen_update.title_left),
+        page: parse_raw_text(&raw_screen_update.page),
+        arrows: raw_screen_update.arrows,
+    }
+}
+
+/// Represents a token of the formatter markup used by the FlyByWire's A32NX mod, e.g.
+/// `{small}{green}FL280{end}{end}`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// Text drawn on the screen, braces that do not form a tag are kept as text
+    Text(&'a str),
+    /// A tag the parser does not know (e.g. `{blink}`), kept as text so no formatter gets closed
+    /// by mistake
+    Unknown(&'a str),
+    /// A tag opening a formatter, which applies until the matching `{end}` tag
+    Open(TextFormatter),
+    /// The `{end}` tag, closing the last formatter opened
+    End,
+    /// The self-closing `{sp}` tag, drawn as a whitespace
+    Space,
+}
+
+impl fmt::Display for Token<'_> {
+    /// Formats the token as it is written in the markup
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Text(text) | Token::Unknown(text) => write!(f, "{}", text),
+            Token::Open(formatter) => write!(f, "{{{}}}", formatter.tag()),
+            Token::End => write!(f, "{{end}}"),
+            Token::Space => write!(f, "{{sp}}"),
+        }
+    }
+}
+
+/// Splits the formatter markup into tokens in a single pass. Writing the tokens back one after
+/// the other gives the original markup
+pub fn tokenize(raw_text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut text_start = 0;
+    let mut pos = 0;
+
+    while let Some(offset) = raw_text[pos..].find('{') {
+        let tag_start = pos + offset;
+
+        // A tag is made of lowercase letters, digits and dashes only, anything else is literal
+        // text
+        let name_start = tag_start + 1;
+        let name_len = raw_text[name_start..]
+            .find(|c: char| !(c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'))
+            .unwrap_or(raw_text.len() - name_start);
+        let name_end = name_start + name_len;
+        let is_closed = raw_text[name_end..].starts_with('}');
+
+        let token = match &raw_text[name_start..name_end] {
+            name if !is_closed || name.is_empty() => None,
+            "end" => Some(Token::End),
+            "sp" => Some(Token::Space),
+            name => Some(TextFormatter::from_tag(name).map_or_else(
+                || Token::Unknown(&raw_text[tag_start..=name_end]),
+                Token::Open,
+            )),
+        };
+
+        match token {
+            Some(token) => {
+                if text_start < tag_start {
+                    tokens.push(Token::Text(&raw_text[text_start..tag_start]));
+                }
+                tokens.push(token);
+
+                pos = name_end + 1;
+                text_start = pos;
+            }
+            None => pos = name_start,
+        }
+    }
+
+    if text_start < raw_text.len() {
+        tokens.push(Token::Text(&raw_text[text_start..]));
+    }
+
+    tokens
+}
+
+/// Parses the formatter markup used by the FlyByWire's A32NX mod into text segments, each with
+/// the formatters applied to it. `{end}` tags with no formatter left to close are ignored, while
+/// unknown tags are reported and drawn as text
+pub fn parse_raw_text(raw_text: &str) -> ParsedText {
+    let mut formatters_stack: Vec<TextFormatter> = Vec::new();
+    let mut result: ParsedText = Vec::new();
+
+    for token in tokenize(raw_text) {
+        let text = match token {
+            Token::Open(formatter) => {
+                formatters_stack.push(formatter);
+                continue;
+            }
+            Token::End => {
+                formatters_stack.pop();
+                continue;
+            }
+            Token::Space => " ",
+            Token::Text(text) => text,
+            Token::Unknown(tag) => {
+                if is_first_report(tag) {
+                    warn!(
+                        "Unknown formatter tag {} in {:?}, drawn as text",
+                        tag, raw_text
+                    );
+                } else {
+                    debug!("Unknown formatter tag {} in {:?}", tag, raw_text);
+                }
+                tag
+            }
+        };
+
+        // Extend the last text segment when the formatting did not change
+        match result.last_mut() {
+            Some(segment) if segment.formatters == formatters_stack => segment.value.push_str(text),
+            _ => result.push(TextSegment {
+                formatters: formatters_stack.clone(),
+                value: text.to_string(),
+            }),
+        }
+    }
+
+    result
+}
+
+/// N. of distinct unknown tags warned about. The tags come from the network, so past this n. they
+/// are no longer remembered and are only logged at debug level
+const MAX_REPORTED_TAGS: usize = 64;
+
+/// Remembers the unknown tags already warned about. The MCDU resends its pages several times per
+/// second, so each unknown tag is warned about only once
+#[derive(Debug, Default)]
+struct ReportedTags {
+    tags: HashSet<String>,
+    is_full: bool,
+}
+
+impl ReportedTags {
+    /// Checks whether the given unknown tag is reported for the first time, remembering it
+    fn is_first_report(&mut self, tag: &str) -> bool {
+        if self.tags.contains(tag) {
+            return false;
+        }
+        if self.tags.len() >= MAX_REPORTED_TAGS {
+            if !self.is_full {
+                warn!(
+                    "More than {} unknown formatter tags, the next ones are only logged at debug \
+                     level",
+                    MAX_REPORTED_TAGS
+                );
+                self.is_full = true;
+            }
+            return false;
+        }
+
+        self.tags.insert(tag.to_string())
+    }
+}
+
+/// Checks whether the given unknown tag is reported for the first time since the application
+/// started
+fn is_first_report(tag: &str) -> bool {
+    static REPORTED_TAGS: OnceLock<Mutex<ReportedTags>> = OnceLock::new();
+
+    REPORTED_TAGS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .is_first_report(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Extracts the text of a parsed section, ignoring its formatting
+    fn text(parsed_text: &ParsedText) -> String {
+        parsed_text
+            .iter()
+            .map(|segment| segment.value.as_str())
+            .collect()
+    }
+
+    /// Checks that the update has the shape expected by the screen
+    fn assert_normalised(screen_update: &ScreenUpdate) {
+        assert_eq!(screen_update.lines.len(), SCREEN_LINES);
+        assert!(screen_update.lines.iter().all(|l| l.len() == LINE_COLUMNS));
+        assert_eq!(screen_update.arrows.len(), SCREEN_ARROWS);
+    }
+
+    #[test]
+    fn short_update_is_padded_with_blanks() {
+        let mcdu_update = parse_update(
+            r#"{
+                "left": {
+                    "lines": [["{green}LEFT{end}"], ["A", "B"]],
+                    "title": "INIT",
+                    "arrows": [true]
+                },
+                "right": {}
+            }"#,
+        )
+        .unwrap();
+
+        let left = &mcdu_update.left;
+        assert_normalised(left);
+        assert_normalised(&mcdu_update.right);
+        assert_eq!(text(&left.lines[0][0]), "LEFT");
+        assert_eq!(text(&left.lines[0][1]), "");
+        assert_eq!(text(&left.lines[1][0]), "A");
+        assert_eq!(text(&left.lines[1][2]), "B");
+        assert!(left.lines[11].iter().all(|column| column.is_empty()));
+        assert_eq!(text(&left.title), "INIT");
+        assert!(left.scratchpad.is_empty());
+        assert_eq!(left.arrows, vec![true, false, false, false]);
+    }
+
+    #[test]
+    fn oversized_update_is_truncated() {
+        let line = r#"["L", "R", "C", "EXTRA"]"#;
+        let json_msg = format!(
+            r#"{{
+                "left": {{ "lines": [{lines}], "arrows": [true, true, true, true, true] }},
+                "right": {{}}
+            }}"#,
+            lines = vec![line; 14].join(",")
+        );
+        let mcdu_update = parse_update(&json_msg).unwrap();
+
+        let left = &mcdu_update.left;
+        assert_normalised(left);
+        assert_eq!(
+            left.lines[11].iter().map(text).collect::<Vec<_>>(),
+            vec!["L", "C", "R"]
+        );
+        assert_eq!(left.arrows, vec![true; SCREEN_ARROWS]);
+    }
+
+    #[test]
+    fn test_message_is_normalised() {
+        let json_msg = fs::read_to_string("test_message.json").unwrap();
+        let mcdu_update = parse_update(&json_msg).unwrap();
+
+        assert_normalised(&mcdu_update.left);
+        assert_normalised(&mcdu_update.right);
+    }
+
+    /// Collects every string contained in a JSON value
+    fn collect_strings(value: &serde_json::Value, strings: &mut Vec<String>) {
+        match value {
+            serde_json::Value::String(string) => strings.push(string.clone()),
+            serde_json::Value::Array(values) => values
+                .iter()
+                .for_each(|value| collect_strings(value, strings)),
+            serde_json::Value::Object(values) => values
+                .values()
+                .for_each(|value| collect_strings(value, strings)),
+            _ => {}
+        }
+    }
+
+    /// Builds a segment with the given formatters
+    fn segment(formatters: &[TextFormatter], value: &str) -> TextSegment {
+        TextSegment {
+            formatters: formatters.to_vec(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_message_strings_are_parsed() {
+        let json_msg = fs::read_to_string("test_message.json").unwrap();
+        let mut raw_texts = Vec::new();
+        collect_strings(&serde_json::from_str(&json_msg).unwrap(), &mut raw_texts);
+        assert!(raw_texts.len() > 50);
+
+        for raw_text in raw_texts {
+            // Writing the tokens back gives the original markup
+            let tokens = tokenize(&raw_text);
+            let markup = tokens.iter().map(Token::to_string).collect::<String>();
+            assert_eq!(markup, raw_text);
+
+            // Every tag is known, so none is left in the text drawn on the screen
+            let tags = [
+                "end", "left", "right", "amber", "cyan", "green", "inop", "magenta", "red",
+                "white", "yellow", "big", "small",
+            ];
+            let expected_text = tags
+                .iter()
+                .fold(raw_text.replace("{sp}", " "), |text, tag| {
+                    text.replace(&format!("{{{}}}", tag), "")
+                });
+            assert_eq!(
+                text(&parse_raw_text(&raw_text)),
+                expected_text,
+                "{}",
+                raw_text
+            );
+        }
+    }
+
+    #[test]
+    fn nested_formatters_are_stacked() {
+        assert_eq!(
+            parse_raw_text("{small}{white}---{end}{green}/{sp}-{end}{end}FL280"),
+            vec![
+                segment(
+                    &[TextFormatter::FontSmall, TextFormatter::ColorWhite],
+                    "---"
+                ),
+                segment(
+                    &[TextFormatter::FontSmall, TextFormatter::ColorGreen],
+                    "/ -"
+                ),
+                segment(&[], "FL280"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_tags_and_braces_are_kept_as_text() {
+        assert_eq!(
+            tokenize("{amber}A{blink}B{end} {x {}"),
+            vec![
+                Token::Open(TextFormatter::ColorAmber),
+                Token::Text("A"),
+                Token::Unknown("{blink}"),
+                Token::Text("B"),
+                Token::End,
+                Token::Text(" {x {}"),
+            ]
+        );
+        assert_eq!(
+            parse_raw_text("{amber}A{blink}B{end} {x {}"),
+            vec![
+                segment(&[TextFormatter::ColorAmber], "A{blink}B"),
+                segment(&[], " {x {}"),
+            ]
+        );
+        assert_eq!(
+            parse_raw_text("{box}{inverse}B{end}{end}{s-text}"),
+            vec![
+                segment(&[TextFormatter::Boxed, TextFormatter::Inverse], "B"),
+                segment(&[], "{s-text}"),
+            ]
+        );
+        assert_eq!(
+            parse_raw_text("{{cyan}}"),
+            vec![segment(&[], "{"), segment(&[TextFormatter::ColorCyan], "}")]
+        );
+    }
+
+    #[test]
+    fn unknown_tags_are_reported_once() {
+        assert!(is_first_report("{reported-once}"));
+        assert!(!is_first_report("{reported-once}"));
+        assert!(is_first_report("{reported-elsewhere}"));
+    }
+
+    #[test]
+    fn reported_tags_are_capped() {
+        let mut reported_tags = ReportedTags::default();
+        for index in 0..MAX_REPORTED_TAGS {
+            assert!(reported_tags.is_first_report(&format!("{{tag-{}}}", index)));
+        }
+
+        assert!(!reported_tags.is_first_report("{tag-0}"));
+        assert!(!reported_tags.is_first_report("{one-too-many}"));
+        assert!(!reported_tags.is_first_report("{one-too-many}"));
+        assert_eq!(reported_tags.tags.len(), MAX_REPORTED_TAGS);
+    }
+
+    #[test]
+    fn unbalanced_tags_are_tolerated() {
+        assert_eq!(
+            parse_raw_text("{end}A{green}B{end}{end}C"),
+            vec![
+                segment(&[], "A"),
+                segment(&[TextFormatter::ColorGreen], "B"),
+                segment(&[], "C"),
+            ]
+        );
+        assert_eq!(
+            parse_raw_text("{cyan}{small}ABC"),
+            vec![segment(
+                &[TextFormatter::ColorCyan, TextFormatter::FontSmall],
+                "ABC"
+            )]
+        );
+        assert!(parse_raw_text("{end}{amber}{end}").is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+pub mod systems;
+
+use crate::{
+    config::parse_key_code,
+    plugins::keyboard::systems::{keyboard_input_system, last_key_feedback_system, setup},
+    protocol::{McduKey, LEFT_LINE_SELECT_KEYS, RIGHT_LINE_SELECT_KEYS},
+};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// Maps the keys of the PC keyboard to the keys of the MCDU's keypad
+#[derive(Deref)]
+pub struct KeyboardMapping(HashMap<KeyCode, McduKey>);
+
+impl KeyboardMapping {
+    /// Builds the default mapping, extended by the given keys. Keys are named after their Bevy key
+    /// code
+    pub fn new(keys: &HashMap<String, McduKey>) -> Self {
+        let mut mapping = default_keys();
+        mapping.extend(keys.iter().map(|(name, key)| (name.clone(), *key)));
+
+        // Names that are not key codes are reported when validating the configuration
+        Self(
+            mapping
+                .into_iter()
+                .filter_map(|(name, key)| Some((parse_key_code(&name)?, key)))
+                .collect(),
+        )
+    }
+}
+
+/// Names the keys of the PC keyboard mapped by default: letters and digits are mapped directly,
+/// F1-F6 to the left line select keys, F7-F12 to the right ones and the named keys to their
+/// closest MCDU key
+fn default_keys() -> HashMap<String, McduKey> {
+    let mut mapping = HashMap::new();
+
+    for letter in 'A'..='Z' {
+        mapping.insert(letter.to_string(), McduKey::Letter(letter));
+    }
+    for digit in 0..=9 {
+        mapping.insert(format!("Key{}", digit), McduKey::Digit(digit));
+        mapping.insert(format!("Numpad{}", digit), McduKey::Digit(digit));
+    }
+
+    let line_select_keys = LEFT_LINE_SELECT_KEYS
+        .into_iter()
+        .chain(RIGHT_LINE_SELECT_KEYS);
+    for (index, key) in line_select_keys.enumerate() {
+        mapping.insert(format!("F{}", index + 1), key);
+    }
+
+    let named_keys = [
+        ("Back", McduKey::Clr),
+        ("Delete", McduKey::Ovfy),
+        ("Space", McduKey::Space),
+        ("Period", McduKey::Dot),
+        ("NumpadDecimal", McduKey::Dot),
+        ("Slash", McduKey::Slash),
+        ("NumpadDivide", McduKey::Slash),
+        ("Minus", McduKey::PlusMinus),
+        ("NumpadSubtract", McduKey::PlusMinus),
+        ("PageUp", McduKey::PrevPage),
+        ("PageDown", McduKey::NextPage),
+        ("Up", McduKey::Up),
+        ("Down", McduKey::Down),
+        ("Home", McduKey::Dir),
+        ("End", McduKey::FPln),
+        ("Insert", McduKey::Init),
+        ("Escape", McduKey::McduMenu),
+    ];
+    for (name, key) in named_keys {
+        mapping.insert(name.to_string(), key);
+    }
+
+    mapping
+}
+
+/// Represents the text showing the last key sent to the MCDU, drawn in debug mode
+#[derive(Component)]
+pub struct LastKeyText;
+
+pub struct KeyboardPlugin;
+
+impl Plugin for KeyboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup)
+            .add_system(keyboard_input_system)
+            .add_system(last_key_feedback_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_keys_are_key_codes() {
+        for name in default_keys().keys() {
+            assert!(parse_key_code(name).is_some(), "{} is not a key code", name);
+        }
+
+        let mapping = KeyboardMapping::new(&HashMap::new());
+        assert_eq!(mapping.len(), default_keys().len());
+        assert_eq!(mapping[&KeyCode::Back], McduKey::Clr);
+        assert_eq!(mapping[&KeyCode::F1], McduKey::L1);
+        assert_eq!(mapping[&KeyCode::F12], McduKey::R6);
+        assert_eq!(mapping[&KeyCode::Key0], McduKey::Digit(0));
+        assert_eq!(mapping[&KeyCode::Q], McduKey::Letter('Q'));
+    }
+
+    #[test]
+    fn configured_keys_replace_the_default_ones() {
+        let keys = HashMap::from([
+            ("F1".to_string(), McduKey::Airport),
+            ("F13".to_string(), McduKey::Ovfy),
+        ]);
+        let mapping = KeyboardMapping::new(&keys);
+
+        assert_eq!(mapping[&KeyCode::F1], McduKey::Airport);
+        assert_eq!(mapping[&KeyCode::F13], McduKey::Ovfy);
+        assert_eq!(mapping[&KeyCode::F2], McduKey::L2);
+    }
+}
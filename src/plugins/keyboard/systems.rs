@@ -0,0 +1,83 @@
+use super::{KeyboardMapping, LastKeyText};
+use crate::{
+    config::Config,
+    keypad::{KeyState, KeypadEvent},
+    plugins::{keypad::KeypadInputEvent, server::McduKeyEvent},
+};
+use bevy::prelude::*;
+
+/// Set-ups the mapping of the PC keyboard, if enabled, and the text showing the last key sent in
+/// debug mode
+pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>, config: Res<Config>) {
+    if config.keyboard.enabled {
+        commands.insert_resource(KeyboardMapping::new(&config.keyboard.keys));
+    }
+
+    if cfg!(feature = "debug-mode") {
+        commands
+            .spawn_bundle(TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        left: Val::Px(8.0),
+                        bottom: Val::Px(8.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                text: Text::with_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load(config.screen.font_small.as_str()),
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                    },
+                    default(),
+                ),
+                ..default()
+            })
+            .insert(LastKeyText);
+    }
+}
+
+/// Presses and releases the keys of the MCDU's keypad mapped to the keys of the PC keyboard. Keys
+/// pressed while CTRL is held are ignored, as it is used by the application's own hotkeys
+pub fn keyboard_input_system(
+    keys: Res<Input<KeyCode>>,
+    mapping: Option<Res<KeyboardMapping>>,
+    mut events: EventWriter<KeypadInputEvent>,
+) {
+    let mapping = match mapping {
+        Some(mapping) => mapping,
+        None => return,
+    };
+
+    let mut key_events = keys
+        .get_just_released()
+        .map(|key_code| (key_code, KeyState::Released))
+        .collect::<Vec<_>>();
+    if !keys.any_pressed([KeyCode::LControl, KeyCode::RControl]) {
+        key_events.extend(
+            keys.get_just_pressed()
+                .map(|key_code| (key_code, KeyState::Pressed)),
+        );
+    }
+
+    for (key_code, state) in key_events {
+        if let Some(key) = mapping.get(key_code) {
+            events.send(KeypadInputEvent(KeypadEvent { key: *key, state }));
+        }
+    }
+}
+
+/// Shows the last key sent to the MCDU, in debug mode only
+pub fn last_key_feedback_system(
+    mut events: EventReader<McduKeyEvent>,
+    mut texts_q: Query<&mut Text, With<LastKeyText>>,
+) {
+    if let Some(McduKeyEvent(key)) = events.iter().last() {
+        for mut text in texts_q.iter_mut() {
+            text.sections[0].value = format!("Last key: {}", key);
+        }
+    }
+}
@@ -0,0 +1,27 @@
+pub mod systems;
+
+use crate::{
+    keypad::{KeypadEvent, KeypadReceiver},
+    plugins::keypad::systems::{key_repeat_system, keypad_events_relay, setup},
+};
+use bevy::prelude::*;
+
+/// Receives the keys pressed and released on the physical keypads, one receiver for each keypad
+/// configured
+#[derive(Deref)]
+pub struct KeypadEventReceivers(Vec<KeypadReceiver>);
+
+/// Represents the event associated with a key of the MCDU's keypad going down or up, on any of the
+/// keypads: physical keypads, the PC keyboard or the touch keys
+pub struct KeypadInputEvent(pub KeypadEvent);
+
+pub struct KeypadPlugin;
+
+impl Plugin for KeypadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<KeypadInputEvent>()
+            .add_startup_system(setup)
+            .add_system(keypad_events_relay)
+            .add_system(key_repeat_system);
+    }
+}
@@ -0,0 +1,82 @@
+use super::{KeypadEventReceivers, KeypadInputEvent};
+use crate::{
+    config::{Config, KeypadConfig},
+    keypad::{
+        repeat::{KeyAction, KeyRepeater},
+        KeypadReceiver,
+    },
+    plugins::server::{McduKeyEvent, McduKeyHeldEvent},
+};
+use bevy::prelude::*;
+
+/// Starts reading the physical keypads that are configured, and tracking the keys held down
+pub fn setup(mut commands: Commands, config: Res<Config>) {
+    commands.insert_resource(KeyRepeater::new(&config.key_repeat));
+    commands.insert_resource(KeypadEventReceivers(start_keypads(&config.keypad)));
+}
+
+/// Starts reading the physical keypads that are configured, returning a receiver for each of them
+#[cfg(feature = "keypad")]
+fn start_keypads(config: &KeypadConfig) -> Vec<KeypadReceiver> {
+    let mut receivers = Vec::new();
+
+    if let Some(device) = &config.serial_device {
+        receivers.push(crate::keypad::serial::start_serial_keypad(
+            device.clone(),
+            config,
+        ));
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(device) = &config.evdev_device {
+        receivers.push(crate::keypad::evdev::start_evdev_keypad(
+            device.clone(),
+            config,
+        ));
+    }
+
+    receivers
+}
+
+#[cfg(not(feature = "keypad"))]
+fn start_keypads(config: &KeypadConfig) -> Vec<KeypadReceiver> {
+    if config.serial_device.is_some() || config.evdev_device.is_some() {
+        warn!("Built without the \"keypad\" feature, the physical keypads are not read");
+    }
+
+    Vec::new()
+}
+
+/// Relays the keys going down and up on the physical keypads
+pub fn keypad_events_relay(
+    receivers: Res<KeypadEventReceivers>,
+    mut events: EventWriter<KeypadInputEvent>,
+) {
+    for receiver in receivers.iter() {
+        events.send_batch(receiver.try_iter().map(KeypadInputEvent));
+    }
+}
+
+/// Presses the keys of the MCDU's keypad going down on any of the keypads, repeating them while
+/// they are held and long pressing the keys that expect it
+pub fn key_repeat_system(
+    mut input_events: EventReader<KeypadInputEvent>,
+    mut repeater: ResMut<KeyRepeater>,
+    time: Res<Time>,
+    mut events: EventWriter<McduKeyEvent>,
+    mut held_events: EventWriter<McduKeyHeldEvent>,
+) {
+    let now = time.time_since_startup();
+
+    let pressed = input_events
+        .iter()
+        .filter_map(|KeypadInputEvent(event)| repeater.handle(*event, now))
+        .collect::<Vec<_>>();
+    let actions = pressed.into_iter().chain(repeater.update(now));
+
+    for action in actions {
+        match action {
+            KeyAction::Press(key) => events.send(McduKeyEvent(key)),
+            KeyAction::LongPress(key) => held_events.send(McduKeyHeldEvent(key)),
+        }
+    }
+}
@@ -1,2 +1,4 @@
+pub mod keyboard;
+pub mod keypad;
 pub mod screen;
 pub mod server;
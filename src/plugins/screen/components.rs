@@ -1,18 +1,43 @@
+use crate::{
+    grid::Grid,
+    protocol::{McduKey, McduSide},
+};
 use bevy::prelude::*;
 
+/// Represents the element containing the screens, covering the part of the window visible
+/// through the bezel
+#[derive(Component)]
+pub struct ScreenContainer;
+
+/// Represents one of the MCDU screens drawn in the window, the root of the screen's rows. Contains
+/// the side of the MCDU being displayed, the grid of cells of the last update received and the
+/// grid last drawn (flashing cells included), used to redraw only the cells that changed
+#[derive(Component)]
+pub struct Screen {
+    pub side: McduSide,
+    pub grid: Option<Grid>,
+    pub drawn_grid: Option<Grid>,
+}
+
+impl Screen {
+    pub fn new(side: McduSide) -> Self {
+        Self {
+            side,
+            grid: None,
+            drawn_grid: None,
+        }
+    }
+}
+
 /// Represents a row of information on the MCDU's screen. Contains the index of the current row
 #[derive(Component)]
 pub struct Row {
     pub row_index: usize,
-    pub is_label: bool,
 }
 
 impl Row {
-    pub fn new(row_index: usize, is_label: bool) -> Self {
-        Self {
-            row_index,
-            is_label,
-        }
+    pub fn new(row_index: usize) -> Self {
+        Self { row_index }
     }
 }
 
@@ -30,3 +55,40 @@ pub struct RowContent;
 /// where the scratchpad and the vertical scroll indicator (if available) are shown
 #[derive(Component)]
 pub struct RowFooter;
+
+/// Represents the text element drawing the glyph of a single cell of a row. Each row has one text
+/// element per column, updated in place
+#[derive(Component)]
+pub struct CellText {
+    pub col: usize,
+}
+
+/// Represents the element drawn behind the text element of a cell, filled with the text color
+/// for inverse and boxed cells. Contains the column of the cell
+#[derive(Component)]
+pub struct CellBackground {
+    pub col: usize,
+}
+
+/// Represents the element covering the background of a boxed cell, leaving only the outline of
+/// the box visible
+#[derive(Component)]
+pub struct CellBoxInset;
+
+/// Represents a key of the MCDU drawn on the window, pressed by touching or clicking it. Contains
+/// the key pressed, whether it is currently pressed and, when both screens are drawn, the side of
+/// the MCDU the key belongs to
+#[derive(Component)]
+pub struct TouchKey {
+    pub key: McduKey,
+    pub side: Option<McduSide>,
+    pub is_pressed: bool,
+}
+
+/// Represents the text element drawing the label of a touch key
+#[derive(Component)]
+pub struct TouchKeyLabel;
+
+/// Represents the column of line select keys on one side of a screen, as tall as the screen
+#[derive(Component)]
+pub struct LineSelectKeys;
@@ -1,15 +1,60 @@
 pub mod components;
 pub mod systems;
-pub(self) mod systems_utils;
+mod systems_utils;
+mod touch_keys;
 
 use self::systems::{
-    clear_screen_system, setup_system, update_content_rows_system, update_footer_row_system,
-    update_header_row_system,
+    brightness_system, draw_screen_system, flash_system, relayout_system, setup_system,
+    sync_screen_side_system, touch_keys_system, update_screen_system,
+    update_status_indicator_system,
+};
+use crate::{
+    brightness::{Backlight, Brightness},
+    plugins::keypad::KeypadInputEvent,
 };
 use bevy::prelude::*;
 
-#[derive(Clone, Hash, Debug, PartialEq, Eq, SystemLabel)]
-struct ClearScreen;
+/// Time in seconds flashing text stays visible, and then hidden, during each flash cycle
+const FLASH_HALF_PERIOD: f32 = 0.5;
+
+/// Holds the fonts used to draw text on the screen, loaded once at startup
+pub struct ScreenFonts {
+    pub big: Handle<Font>,
+    pub small: Handle<Font>,
+}
+
+/// Holds the phase of flashing text. It is shared by all the screens so that text flashes in sync,
+/// and keeps running across updates so that text flashing on consecutive pages does not restart
+pub struct FlashPhase {
+    pub timer: Timer,
+    pub visible: bool,
+}
+
+impl Default for FlashPhase {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(FLASH_HALF_PERIOD, true),
+            visible: true,
+        }
+    }
+}
+
+/// Holds the brightness of the screen, along with the backlight it drives if there is one
+pub struct ScreenBrightness {
+    pub brightness: Brightness,
+    pub backlight: Option<Backlight>,
+}
+
+impl ScreenBrightness {
+    /// Computes the factor the colors of the screen are scaled by. The backlight dims the panel
+    /// itself, so colors are dimmed only when there is none
+    pub fn color_factor(&self) -> f32 {
+        match self.backlight {
+            Some(_) => 1.0,
+            None => self.brightness.factor(),
+        }
+    }
+}
 
 #[derive(Clone, Hash, Debug, PartialEq, Eq, SystemLabel)]
 struct UpdateScreen;
@@ -18,15 +63,17 @@ pub struct ScreenPlugin;
 
 impl Plugin for ScreenPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_system)
-            .add_system(clear_screen_system.label(ClearScreen).before(UpdateScreen))
-            .add_system_set(
-                SystemSet::new()
-                    .label(UpdateScreen)
-                    .after(ClearScreen)
-                    .with_system(update_header_row_system)
-                    .with_system(update_content_rows_system)
-                    .with_system(update_footer_row_system),
-            );
+        // The touch keys send their keys like the keypads, whether the keypad plugin is added or not
+        app.init_resource::<FlashPhase>()
+            .add_event::<KeypadInputEvent>()
+            .add_startup_system(setup_system)
+            .add_system(sync_screen_side_system.before(UpdateScreen))
+            .add_system(update_status_indicator_system)
+            .add_system(touch_keys_system)
+            .add_system(update_screen_system.label(UpdateScreen))
+            .add_system(flash_system.label(UpdateScreen))
+            .add_system(relayout_system.label(UpdateScreen))
+            .add_system(brightness_system.label(UpdateScreen))
+            .add_system(draw_screen_system.after(UpdateScreen));
     }
 }
@@ -1,240 +1,503 @@
 use super::{
-    components::{Row, RowContent, RowFooter, RowHeader},
+    components::{
+        CellBackground, CellBoxInset, CellText, LineSelectKeys, Row, RowContent, RowFooter,
+        RowHeader, Screen, ScreenContainer, TouchKey, TouchKeyLabel,
+    },
     systems_utils::{
-        compute_font_size, compute_font_whitespace, compute_row_width, compute_text_bundles,
-        TextAlign,
+        compute_box_inset, compute_cell_background_color, compute_cell_position,
+        compute_cell_section, compute_cell_size, compute_container_layout,
+        compute_container_transform, compute_font_size, compute_row_padding, compute_screen_area,
+        compute_screen_color, compute_screen_size,
+    },
+    touch_keys::{
+        compute_line_select_keys_size, spawn_touch_panel, KEY_COLOR, KEY_HOVERED_COLOR,
+        KEY_PRESSED_COLOR, LABEL_FONT_SCALE,
     },
+    FlashPhase, ScreenBrightness, ScreenFonts,
 };
 use crate::{
-    plugins::server::{ScreenUpdateEvent, TextSegment},
-    SCREEN_ROWS,
+    brightness::{Backlight, Brightness},
+    config::{Config, ScreenConfig, ScreenMode},
+    grid::{compute_box_edges, Grid, RowKind},
+    keypad::{KeyState, KeypadEvent},
+    plugins::{
+        keypad::KeypadInputEvent,
+        server::{McduKeyEvent, ScreenUpdateEvent, ServerStatus},
+    },
+    protocol::{McduKey, McduSide},
+};
+use bevy::{
+    prelude::*,
+    window::{WindowId, WindowResized},
 };
-use bevy::prelude::*;
 use rand::Rng;
 
 /// Set-ups the UI hierarchy
-pub fn setup_system(mut commands: Commands, windows: Res<Windows>) {
+pub fn setup_system(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    asset_server: Res<AssetServer>,
+    config: Res<Config>,
+    side: Res<McduSide>,
+) {
     let mut rng = rand::thread_rng();
 
     let window = windows.get_primary().unwrap();
-    let window_height = window.height();
-
-    // Compute the width of the container element to show at most SCREEN_COLS characters of text
-    let font_size = compute_font_size(window);
-    let font_whitespace = compute_font_whitespace(font_size);
-    let row_height = window_height / (SCREEN_ROWS as f32);
-    let row_width = compute_row_width(font_size);
-
-    // Root container
-    #[rustfmt::skip]
-    let root_color = if cfg!(feature = "debug-mode") { Color::TEAL } else { Color::NONE };
-    let root = commands
+    let cfg = &config.screen;
+
+    // Load the fonts once, they are shared by all the text elements
+    let fonts = ScreenFonts {
+        big: asset_server.load(cfg.font.as_str()),
+        small: asset_server.load(cfg.font_small.as_str()),
+    };
+    let label_font = fonts.small.clone();
+    commands.insert_resource(fonts);
+
+    // Restore the brightness set before the last shutdown
+    let brightness = ScreenBrightness {
+        brightness: Brightness::load(&config.brightness.file),
+        backlight: config.brightness.backlight.as_ref().and_then(|path| {
+            Backlight::open(path)
+                .map_err(|err| warn!("Cannot open the backlight {}: {}", path.display(), err))
+                .ok()
+        }),
+    };
+    apply_brightness(&brightness, &mut commands, cfg);
+    commands.insert_resource(brightness);
+
+    // Compute the width of the container element to show at most `cols` characters of text
+    let area = compute_screen_area(window, &config.bezel);
+    let font_size = compute_font_size(&area, &config);
+    let row_height = cfg.row_height(font_size);
+
+    // Window container, lays out the screens next to each other within the bezel
+    let (position, size) = compute_container_layout(&area);
+    let container = commands
         .spawn_bundle(NodeBundle {
             style: Style {
-                position_type: PositionType::Relative,
-                flex_direction: FlexDirection::ColumnReverse,
-                margin: Rect {
-                    left: Val::Auto,
-                    right: Val::Auto,
-                    top: Val::Undefined,
-                    bottom: Val::Undefined,
-                },
-                size: Size::new(Val::Px(row_width + font_whitespace), Val::Px(window_height)),
+                position_type: PositionType::Absolute,
+                position,
+                size,
+                flex_direction: FlexDirection::Row,
+                justify_content: JustifyContent::SpaceEvenly,
+                align_items: AlignItems::Center,
                 ..default()
             },
-            color: UiColor(root_color),
+            color: UiColor(Color::NONE),
+            transform: compute_container_transform(&config.bezel),
             ..default()
         })
+        .insert(ScreenContainer)
         .id();
 
-    // Screen rows
-    for row_index in 0..SCREEN_ROWS {
-        let is_label = row_index % 2 != 0;
+    let sides = match config.mcdu.mode {
+        ScreenMode::Single => vec![*side],
+        ScreenMode::Dual => vec![McduSide::Left, McduSide::Right],
+    };
 
-        #[rustfmt::skip]
-        let color_alpha = if cfg!(feature = "debug-mode") { 0.25 } else { 0.0 };
-        let mut screen_row = commands.spawn_bundle(NodeBundle {
-            style: Style {
-                position_type: PositionType::Relative,
-                padding: Rect {
-                    left: Val::Px(font_whitespace),
-                    right: Val::Undefined,
-                    top: Val::Undefined,
-                    bottom: Val::Undefined,
+    let screen_count = sides.len();
+    for side in sides {
+        // In touch mode each screen is surrounded by its line select keys, with a keypad below
+        let parent = if config.window.touch_keys {
+            let key_side = match config.mcdu.mode {
+                ScreenMode::Single => None,
+                ScreenMode::Dual => Some(side),
+            };
+            spawn_touch_panel(
+                &mut commands,
+                container,
+                key_side,
+                screen_count,
+                &label_font,
+                font_size,
+                cfg,
+            )
+        } else {
+            container
+        };
+
+        // Root container
+        let root_color = compute_screen_color(false);
+        let root = commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Relative,
+                    flex_direction: FlexDirection::ColumnReverse,
+                    size: compute_screen_size(font_size, cfg),
+                    ..default()
                 },
-                size: Size::new(Val::Percent(100.0), Val::Px(row_height)),
+                color: UiColor(root_color),
                 ..default()
-            },
-            color: UiColor(Color::rgba(
-                rng.gen_range(0.0..=1.0),
-                rng.gen_range(0.0..=1.0),
-                rng.gen_range(0.0..=1.0),
-                color_alpha,
-            )),
-            ..default()
-        });
-
-        screen_row
-            .insert(Row::new(row_index, is_label))
-            .insert(Parent(root));
-
-        if row_index == 0 {
-            // Header row
-            screen_row.insert(RowHeader);
-        } else if row_index == SCREEN_ROWS - 1 {
-            // Footer row
-            screen_row.insert(RowFooter);
-        } else {
-            // Content rows
-            screen_row.insert(RowContent);
+            })
+            .insert(Screen::new(side))
+            .insert(Parent(parent))
+            .id();
+
+        // Screen rows
+        for row_index in 0..cfg.rows {
+            #[rustfmt::skip]
+            let color_alpha = if cfg!(feature = "debug-mode") { 0.25 } else { 0.0 };
+            let mut screen_row = commands.spawn_bundle(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Relative,
+                    padding: compute_row_padding(font_size, cfg),
+                    size: Size::new(Val::Percent(100.0), Val::Px(row_height)),
+                    ..default()
+                },
+                color: UiColor(Color::rgba(
+                    rng.gen_range(0.0..=1.0),
+                    rng.gen_range(0.0..=1.0),
+                    rng.gen_range(0.0..=1.0),
+                    color_alpha,
+                )),
+                ..default()
+            });
+
+            screen_row.insert(Row::new(row_index)).insert(Parent(root));
+
+            match RowKind::of_row(row_index, cfg.rows) {
+                RowKind::Header => screen_row.insert(RowHeader),
+                RowKind::Content => screen_row.insert(RowContent),
+                RowKind::Footer => screen_row.insert(RowFooter),
+            };
+            let row = screen_row.id();
+
+            // Elements of the row, one background and one text element for each column. Backgrounds
+            // are spawned first so that they are drawn below the text
+            for col in 0..cfg.cols {
+                let background = commands
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            position: compute_cell_position(col, font_size, cfg),
+                            size: compute_cell_size(font_size, cfg),
+                            ..default()
+                        },
+                        color: UiColor(Color::NONE),
+                        ..default()
+                    })
+                    .insert(CellBackground { col })
+                    .insert(Parent(row))
+                    .id();
+                commands
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            position: Rect::all(Val::Px(0.0)),
+                            ..default()
+                        },
+                        color: UiColor(Color::NONE),
+                        ..default()
+                    })
+                    .insert(CellBoxInset)
+                    .insert(Parent(background));
+
+                commands
+                    .spawn_bundle(TextBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            position: compute_cell_position(col, font_size, cfg),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .insert(CellText { col })
+                    .insert(Parent(row));
+            }
         }
     }
 }
 
-/// Clears the screen before each update gets rendered
-pub fn clear_screen_system(
-    mut commands: Commands,
-    mut events: EventReader<ScreenUpdateEvent>,
-    rows_q: Query<Entity, With<Row>>,
+/// Keeps the screen in sync with the selected MCDU side when a single screen is drawn
+pub fn sync_screen_side_system(
+    config: Res<Config>,
+    side: Res<McduSide>,
+    mut screens_q: Query<&mut Screen>,
 ) {
-    events.iter().for_each(|_| {
-        rows_q.for_each(|e| commands.entity(e).despawn_descendants());
-    });
+    if config.mcdu.mode != ScreenMode::Single {
+        return;
+    }
+
+    for mut screen in screens_q.iter_mut() {
+        if screen.side != *side {
+            screen.side = *side;
+        }
+    }
 }
 
-/// Updates the header section of the screen
-pub fn update_header_row_system(
-    mut commands: Commands,
-    mut events: EventReader<ScreenUpdateEvent>,
-    header_row_q: Query<Entity, (With<Row>, With<RowHeader>)>,
-    asset_server: Res<AssetServer>,
+/// Shows whether the last update sent by the MCDU was rejected by tinting the screens
+pub fn update_status_indicator_system(
+    status: Res<ServerStatus>,
+    mut screens_q: Query<&mut UiColor, With<Screen>>,
+) {
+    if !status.is_changed() {
+        return;
+    }
+
+    let color = compute_screen_color(status.last_update_error.is_some());
+    for mut screen_color in screens_q.iter_mut() {
+        screen_color.0 = color;
+    }
+}
+
+/// Resizes the screens, their rows and cells to fit the primary window when it gets resized (e.g.
+/// when the display mode changes), and redraws every cell with the new font size
+#[allow(clippy::type_complexity)]
+pub fn relayout_system(
+    mut events: EventReader<WindowResized>,
+    mut styles: ParamSet<(
+        Query<&mut Style, With<ScreenContainer>>,
+        Query<(&mut Screen, &mut Style)>,
+        Query<&mut Style, With<Row>>,
+        Query<(&CellBackground, &mut Style)>,
+        Query<(&CellText, &mut Style)>,
+        Query<&mut Style, With<LineSelectKeys>>,
+    )>,
+    mut labels_q: Query<&mut Text, With<TouchKeyLabel>>,
     windows: Res<Windows>,
+    config: Res<Config>,
 ) {
-    for screen_update_event in events.iter() {
-        let header_row = header_row_q.get_single().unwrap();
-        let window = windows.get_primary().unwrap();
-        let screen_update = &screen_update_event.0;
-
-        // Update the title of the current page
-        let title = &screen_update.title;
-        compute_text_bundles(title, TextAlign::Center, false, &asset_server, &window)
-            .into_iter()
-            .for_each(|b| {
-                commands.spawn_bundle(b).insert(Parent(header_row));
-            });
+    if !events.iter().any(|event| event.id == WindowId::primary()) {
+        return;
+    }
 
-        // Update the left title
-        let title_left = &screen_update.title_left;
-        compute_text_bundles(title_left, TextAlign::Left, false, &asset_server, &window)
-            .into_iter()
-            .for_each(|b| {
-                commands.spawn_bundle(b).insert(Parent(header_row));
-            });
+    let window = windows.get_primary().unwrap();
+    let cfg = &config.screen;
+    let area = compute_screen_area(window, &config.bezel);
+    let font_size = compute_font_size(&area, &config);
+    let row_height = cfg.row_height(font_size);
+    debug!(
+        "Window resized to {}x{}, laying out the screens with a font size of {}",
+        window.width(),
+        window.height(),
+        font_size
+    );
 
-        // Update the page indicator
-        let page = &screen_update.page;
-        if !page.is_empty() {
-            // Render the current page
-            compute_text_bundles(page, TextAlign::Right, false, &asset_server, &window)
-                .into_iter()
-                .for_each(|b| {
-                    commands.spawn_bundle(b).insert(Parent(header_row));
-                });
-        } else {
-            // Render horizontal arrows instead
-            let render_sx = screen_update.arrows[2];
-            let render_dx = screen_update.arrows[3];
-            let arrows = vec![
-                TextSegment {
-                    formatters: Vec::new(),
-                    value: (if render_sx { "←" } else { "" }).to_string(),
-                },
-                TextSegment {
-                    formatters: Vec::new(),
-                    value: (if render_dx { "→" } else { "" }).to_string(),
-                },
-            ];
+    for mut style in styles.p0().iter_mut() {
+        (style.position, style.size) = compute_container_layout(&area);
+    }
+    for (mut screen, mut style) in styles.p1().iter_mut() {
+        style.size = compute_screen_size(font_size, cfg);
+
+        // Forget the cells drawn so far, their glyphs have to be drawn again at the new size
+        screen.drawn_grid = None;
+    }
+    for mut style in styles.p2().iter_mut() {
+        style.padding = compute_row_padding(font_size, cfg);
+        style.size.height = Val::Px(row_height);
+    }
+    for (background, mut style) in styles.p3().iter_mut() {
+        style.position = compute_cell_position(background.col, font_size, cfg);
+        style.size = compute_cell_size(font_size, cfg);
+    }
+    for (cell_text, mut style) in styles.p4().iter_mut() {
+        style.position = compute_cell_position(cell_text.col, font_size, cfg);
+    }
+    for mut style in styles.p5().iter_mut() {
+        style.size = compute_line_select_keys_size(font_size, cfg);
+    }
+    for mut text in labels_q.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.style.font_size = font_size * LABEL_FONT_SCALE;
+        }
+    }
+}
+
+/// Presses the keys of the MCDU touched or clicked on the window and releases them when they are
+/// no longer pressed, highlighting them while they are hovered or pressed. When both screens are
+/// drawn, the side of the key pressed is selected first
+pub fn touch_keys_system(
+    mut keys_q: Query<(&Interaction, &mut TouchKey, &mut UiColor), Changed<Interaction>>,
+    mut side: ResMut<McduSide>,
+    mut events: EventWriter<KeypadInputEvent>,
+) {
+    for (interaction, mut touch_key, mut color) in keys_q.iter_mut() {
+        color.0 = match interaction {
+            Interaction::Clicked => KEY_PRESSED_COLOR,
+            Interaction::Hovered => KEY_HOVERED_COLOR,
+            Interaction::None => KEY_COLOR,
+        };
 
-            compute_text_bundles(&arrows, TextAlign::Right, false, &asset_server, &window)
-                .into_iter()
-                .for_each(|b| {
-                    commands.spawn_bundle(b).insert(Parent(header_row));
-                });
+        // Only the keys pressed on the window are released, hovering other keys sends nothing
+        let state = match (interaction, touch_key.is_pressed) {
+            (Interaction::Clicked, false) => KeyState::Pressed,
+            (Interaction::Hovered | Interaction::None, true) => KeyState::Released,
+            _ => continue,
+        };
+        touch_key.is_pressed = state == KeyState::Pressed;
+        if state == KeyState::Pressed {
+            if let Some(key_side) = touch_key.side {
+                if *side != key_side {
+                    *side = key_side;
+                }
+            }
         }
+        events.send(KeypadInputEvent(KeypadEvent {
+            key: touch_key.key,
+            state,
+        }));
     }
 }
 
-/// Updates the main content section
-pub fn update_content_rows_system(
+/// Changes the brightness of the screen when the BRT and DIM keys are pressed, saving it so that it
+/// is restored on the next start
+pub fn brightness_system(
     mut commands: Commands,
+    mut events: EventReader<McduKeyEvent>,
+    mut brightness: ResMut<ScreenBrightness>,
+    mut screens_q: Query<&mut Screen>,
+    config: Res<Config>,
+) {
+    let mut level = brightness.brightness;
+    for McduKeyEvent(key) in events.iter() {
+        match key {
+            McduKey::Brt => level = level.brighter(),
+            McduKey::Dim => level = level.dimmer(),
+            _ => (),
+        }
+    }
+    if level == brightness.brightness {
+        return;
+    }
+
+    info!("Brightness set to {}", level.level());
+    brightness.brightness = level;
+    if let Err(err) = level.save(&config.brightness.file) {
+        warn!(
+            "Cannot save the brightness to {}: {}",
+            config.brightness.file.display(),
+            err
+        );
+    }
+    apply_brightness(&brightness, &mut commands, &config.screen);
+
+    // Forget the cells drawn so far, their colors have to be scaled again
+    for mut screen in screens_q.iter_mut() {
+        screen.drawn_grid = None;
+    }
+}
+
+/// Sets the backlight to the given brightness if there is one, and dims the window background
+fn apply_brightness(brightness: &ScreenBrightness, commands: &mut Commands, cfg: &ScreenConfig) {
+    if let Some(backlight) = &brightness.backlight {
+        if let Err(err) = backlight.set(brightness.brightness) {
+            warn!("Cannot set the backlight brightness: {}", err);
+        }
+    }
+
+    let background = cfg.background.dimmed(brightness.color_factor());
+    commands.insert_resource(ClearColor(background.into()));
+}
+
+/// Lays out the updates sent by the MCDU on a grid of cells, for the screens showing their side
+pub fn update_screen_system(
     mut events: EventReader<ScreenUpdateEvent>,
-    content_rows_q: Query<(Entity, &Row), (With<Row>, With<RowContent>)>,
-    asset_server: Res<AssetServer>,
-    windows: Res<Windows>,
+    mut screens_q: Query<&mut Screen>,
+    config: Res<Config>,
 ) {
-    for screen_update_event in events.iter() {
-        let window = windows.get_primary().unwrap();
-        let screen_update = &screen_update_event.0;
-
-        for (row_entity, row) in content_rows_q.iter() {
-            let line = &screen_update.lines[row.row_index - 1];
-            for (col_index, parsed_text) in line.iter().enumerate() {
-                let align = match col_index {
-                    0 => TextAlign::Left,
-                    1 => TextAlign::Center,
-                    2 => TextAlign::Right,
-                    _ => TextAlign::Left,
-                };
-
-                compute_text_bundles(parsed_text, align, row.is_label, &asset_server, &window)
-                    .into_iter()
-                    .for_each(|b| {
-                        commands.spawn_bundle(b).insert(Parent(row_entity));
-                    });
+    let cfg = &config.screen;
+
+    for ScreenUpdateEvent { side, update } in events.iter() {
+        let grid = Grid::from_update(update, cfg.rows, cfg.cols);
+
+        for mut screen in screens_q.iter_mut() {
+            if screen.side == *side && screen.grid.as_ref() != Some(&grid) {
+                screen.grid = Some(grid.clone());
             }
         }
     }
 }
 
-/// Updates the header section of the screen
-pub fn update_footer_row_system(
-    mut commands: Commands,
-    mut events: EventReader<ScreenUpdateEvent>,
-    footer_row_q: Query<Entity, (With<Row>, With<RowFooter>)>,
-    asset_server: Res<AssetServer>,
+/// Toggles the visibility of flashing text at a steady pace
+pub fn flash_system(time: Res<Time>, mut phase: ResMut<FlashPhase>) {
+    if phase.timer.tick(time.delta()).just_finished() {
+        phase.visible = !phase.visible;
+    }
+}
+
+/// Redraws the cells of the screens that changed since they were last drawn, either because of a
+/// new update or because flashing text was shown or hidden, replacing the glyph and background of
+/// their elements in place
+#[allow(clippy::too_many_arguments)]
+pub fn draw_screen_system(
+    mut screens_q: Query<(Entity, &mut Screen)>,
+    rows_q: Query<(&Row, &Parent, &Children)>,
+    mut texts_q: Query<(&CellText, &mut Text)>,
+    mut backgrounds_q: Query<(&CellBackground, &Children, &mut UiColor), Without<CellBoxInset>>,
+    mut insets_q: Query<(&mut Style, &mut UiColor), With<CellBoxInset>>,
+    fonts: Res<ScreenFonts>,
+    phase: Res<FlashPhase>,
+    brightness: Res<ScreenBrightness>,
     windows: Res<Windows>,
+    config: Res<Config>,
 ) {
-    for screen_update_event in events.iter() {
-        let footer_row = footer_row_q.get_single().unwrap();
-        let window = windows.get_primary().unwrap();
-        let screen_update = &screen_update_event.0;
-
-        // Update the scratchpad
-        let scratchpad = &screen_update.scratchpad;
-        compute_text_bundles(scratchpad, TextAlign::Left, false, &asset_server, &window)
-            .into_iter()
-            .for_each(|b| {
-                commands.spawn_bundle(b).insert(Parent(footer_row));
-            });
+    let window = windows.get_primary().unwrap();
+    let cfg = &config.screen;
+    let area = compute_screen_area(window, &config.bezel);
+    let font_size = compute_font_size(&area, &config);
+    let phase_toggled = phase.timer.just_finished();
+    let color_factor = brightness.color_factor();
 
-        // Update the vertical scroll indicator
-        let render_up = screen_update.arrows[0];
-        let render_down = screen_update.arrows[1];
-        let arrows = vec![
-            TextSegment {
-                formatters: Vec::new(),
-                value: (if render_down { "↓" } else { "" }).to_string(),
-            },
-            TextSegment {
-                formatters: Vec::new(),
-                value: (if render_up { "↑" } else { "" }).to_string(),
-            },
-        ];
+    for (screen_entity, mut screen) in screens_q.iter_mut() {
+        if !screen.is_changed() && !phase_toggled {
+            continue;
+        }
 
-        compute_text_bundles(&arrows, TextAlign::Right, false, &asset_server, &window)
-            .into_iter()
-            .for_each(|b| {
-                commands.spawn_bundle(b).insert(Parent(footer_row));
-            });
+        let grid = match &screen.grid {
+            Some(grid) => grid.at_flash_phase(phase.visible),
+            None => continue,
+        };
+        let changed_cells = grid.changed_cells(screen.drawn_grid.as_ref());
+        if changed_cells.is_empty() {
+            continue;
+        }
+
+        let mut redrawn_cells = 0;
+        let screen_rows = rows_q
+            .iter()
+            .filter(|(_, parent, _)| parent.0 == screen_entity);
+
+        for (row, _, children) in screen_rows {
+            let cells = grid.row(row.row_index);
+            let is_unchanged = |col: usize| !changed_cells.contains(&(row.row_index, col));
+
+            for child in children.iter() {
+                if let Ok((cell_text, mut text)) = texts_q.get_mut(*child) {
+                    if is_unchanged(cell_text.col) {
+                        continue;
+                    }
+
+                    let cell = &cells[cell_text.col];
+                    text.sections = vec![compute_cell_section(
+                        cell,
+                        &fonts,
+                        font_size,
+                        color_factor,
+                        cfg,
+                    )];
+                    redrawn_cells += 1;
+                } else if let Ok((background, inset, mut color)) = backgrounds_q.get_mut(*child) {
+                    if is_unchanged(background.col) {
+                        continue;
+                    }
+
+                    let cell = &cells[background.col];
+                    color.0 = compute_cell_background_color(cell, color_factor, cfg);
+
+                    let edges = compute_box_edges(cells, background.col);
+                    let (position, inset_color) =
+                        compute_box_inset(cell, edges, font_size, color_factor, cfg);
+                    for inset in inset.iter() {
+                        if let Ok((mut style, mut color)) = insets_q.get_mut(*inset) {
+                            style.position = position;
+                            color.0 = inset_color;
+                        }
+                    }
+                }
+            }
+        }
+
+        debug!("Redrew {} cells of the {} MCDU", redrawn_cells, screen.side);
+        screen.drawn_grid = Some(grid);
     }
 }
@@ -1,162 +1,186 @@
+use super::{
+    touch_keys::{KEYPAD_HEIGHT, LINE_SELECT_KEYS_WIDTH},
+    ScreenFonts,
+};
 use crate::{
-    plugins::server::{ParsedText, TextFormatter, TextSegment},
-    SCREEN_COLS, SCREEN_ROWS,
+    config::{BezelConfig, Config, ScreenArea, ScreenConfig},
+    grid::{BoxEdges, Cell, CellSize},
 };
 use bevy::prelude::*;
-use unicode_segmentation::UnicodeSegmentation;
 
-const FONT_ASPECT_RATIO: f32 = 1.3850;
-const FONT_SIZE_PERCENT: f32 = 0.90;
+/// Computes the area of the window the screens are laid out in, inside the bezel
+pub(super) fn compute_screen_area(window: &Window, bezel: &BezelConfig) -> ScreenArea {
+    bezel.screen_area(window.width(), window.height())
+}
 
-/// Computes the font size given the window where text will be displayed
-pub(super) fn compute_font_size(window: &Window) -> f32 {
-    let window_height = window.height();
-    let font_size = window_height / (SCREEN_ROWS as f32) * FONT_SIZE_PERCENT;
+/// Computes the position and size of the element containing the screens, covering the given area
+pub(super) fn compute_container_layout(area: &ScreenArea) -> (Rect<Val>, Size<Val>) {
+    // Setting `bottom` to the top inset is intentional: the y-axis of the UI points up, so the
+    // top of the window is the "bottom" of the layout. Using `top` would swap the bezel insets
+    let position = Rect {
+        left: Val::Px(area.left),
+        right: Val::Undefined,
+        top: Val::Undefined,
+        bottom: Val::Px(area.top),
+    };
 
-    font_size
+    (
+        position,
+        Size::new(Val::Px(area.width), Val::Px(area.height)),
+    )
 }
 
-/// Computes the horizontal whitespace between the end of a grapheme and the start of the next
-/// one
-pub(super) fn compute_font_whitespace(font_size: f32) -> f32 {
-    font_size - (font_size / FONT_ASPECT_RATIO)
+/// Computes the transform rotating and mirroring the element containing the screens around its
+/// centre, as the panel is mounted behind the bezel
+pub(super) fn compute_container_transform(bezel: &BezelConfig) -> Transform {
+    let flip = |is_flipped: bool| if is_flipped { -1.0 } else { 1.0 };
+
+    Transform {
+        // Positive angles rotate counter-clockwise
+        rotation: Quat::from_rotation_z(-bezel.rotation.degrees().to_radians()),
+        scale: Vec3::new(flip(bezel.flip_horizontal), flip(bezel.flip_vertical), 1.0),
+        ..default()
+    }
 }
 
-/// Computes the width of a single row based on the font size and the n. of columns we have to
-/// display
-pub(super) fn compute_row_width(font_size: f32) -> f32 {
-    (font_size / FONT_ASPECT_RATIO) * (SCREEN_COLS as f32)
+/// Computes the font size given the area where the screens are laid out side by side. When touch
+/// keys are drawn, each screen only gets the space left between its line select keys and above its
+/// keypad
+pub(super) fn compute_font_size(area: &ScreenArea, config: &Config) -> f32 {
+    let cfg = &config.screen;
+    let (mut width, mut height) = (area.width, area.height);
+    if config.window.touch_keys {
+        width *= 1.0 - 2.0 * LINE_SELECT_KEYS_WIDTH;
+        height *= 1.0 - KEYPAD_HEIGHT;
+    }
+
+    let height_font_size = height / (cfg.rows as f32) * cfg.font_size_percent;
+
+    // Each screen is as wide as a row plus the whitespace used to pad it on the left side
+    let screen_width = width / (config.mcdu.mode.screen_count() as f32);
+    let width_font_size = screen_width / ((cfg.cols as f32 - 1.0) / cfg.font_aspect_ratio + 1.0);
+
+    height_font_size.min(width_font_size)
 }
 
-#[derive(Clone, Copy)]
-pub(super) enum TextAlign {
-    Left,
-    Center,
-    Right,
+/// Computes the background color of a screen, tinted in red when the last update sent by the MCDU
+/// was rejected
+pub(super) fn compute_screen_color(update_rejected: bool) -> Color {
+    if update_rejected {
+        Color::rgba(1.0, 0.0, 0.0, 0.15)
+    } else if cfg!(feature = "debug-mode") {
+        Color::TEAL
+    } else {
+        Color::NONE
+    }
 }
 
-/// Computes all the TextBundles that make up the given parsed text
-pub(super) fn compute_text_bundles(
-    parsed_text: &ParsedText,
-    default_alignment: TextAlign,
-    is_label_row: bool,
-    asset_server: &AssetServer,
-    window: &Window,
-) -> Vec<TextBundle> {
-    let mut text_bundles: Vec<TextBundle> = Vec::new();
-    let mut left_text_sections: Vec<TextSection> = Vec::new();
-    let mut center_text_sections: Vec<TextSection> = Vec::new();
-    let mut right_text_sections: Vec<TextSection> = Vec::new();
-
-    let font_size = compute_font_size(window);
-    let font_whitespace = compute_font_whitespace(font_size);
-    let row_width = compute_row_width(font_size);
-
-    for TextSegment { formatters, value } in parsed_text {
-        let mut font_name = if is_label_row {
-            "HoneywellMCDUSmall.ttf"
+/// Computes the text section used to draw the glyph of a cell, blank cells draw nothing. Colors
+/// are scaled by the given brightness factor
+pub(super) fn compute_cell_section(
+    cell: &Cell,
+    fonts: &ScreenFonts,
+    font_size: f32,
+    color_factor: f32,
+    cfg: &ScreenConfig,
+) -> TextSection {
+    let font = match cell.size {
+        CellSize::Big => &fonts.big,
+        CellSize::Small => &fonts.small,
+    };
+
+    TextSection {
+        value: if cell.is_blank() {
+            String::new()
         } else {
-            "HoneywellMCDU.ttf"
-        };
-        let mut color = Color::rgb_u8(0xff, 0xff, 0xff);
-        let mut align = default_alignment;
-
-        for formatter in formatters {
-            // Extract which font to use
-            font_name = match formatter {
-                TextFormatter::FontBig => "HoneywellMCDU.ttf",
-                TextFormatter::FontSmall => "HoneywellMCDUSmall.ttf",
-                _ => font_name,
-            };
+            cell.char.to_string()
+        },
+        style: TextStyle {
+            font: font.clone(),
+            font_size,
+            color: if cell.inverse {
+                cfg.background.dimmed(color_factor).into()
+            } else {
+                cfg.colors.color(cell.color).dimmed(color_factor).into()
+            },
+        },
+    }
+}
 
-            // Extract which color to use
-            color = match formatter {
-                TextFormatter::ColorAmber => Color::rgb_u8(0xff, 0x9a, 0x00),
-                TextFormatter::ColorCyan => Color::rgb_u8(0x00, 0xff, 0xff),
-                TextFormatter::ColorGreen => Color::rgb_u8(0x00, 0xff, 0x00),
-                TextFormatter::ColorInop => Color::rgb_u8(0x66, 0x66, 0x66),
-                TextFormatter::ColorMagenta => Color::rgb_u8(0xff, 0x94, 0xff),
-                TextFormatter::ColorRed => Color::rgb_u8(0xff, 0x00, 0x00),
-                TextFormatter::ColorWhite => Color::rgb_u8(0xff, 0xff, 0xff),
-                TextFormatter::ColorYellow => Color::rgb_u8(0xff, 0xff, 0x00),
-                _ => color,
-            };
+/// Computes the color of the background of a cell, filled with the text color for inverse and
+/// boxed cells
+pub(super) fn compute_cell_background_color(
+    cell: &Cell,
+    color_factor: f32,
+    cfg: &ScreenConfig,
+) -> Color {
+    if cell.inverse || cell.boxed {
+        cfg.colors.color(cell.color).dimmed(color_factor).into()
+    } else {
+        Color::NONE
+    }
+}
 
-            // Extract which alignment to use
-            match formatter {
-                #[rustfmt::skip]
-                TextFormatter::AlignLeft => { align = TextAlign::Left; }
-                #[rustfmt::skip]
-                TextFormatter::AlignRight => { align = TextAlign::Right; }
-                _ => {}
+/// Computes the position and color of the inset covering the background of a cell. Boxed cells
+/// are covered by the screen background except for the sides of the box outline
+pub(super) fn compute_box_inset(
+    cell: &Cell,
+    edges: Option<BoxEdges>,
+    font_size: f32,
+    color_factor: f32,
+    cfg: &ScreenConfig,
+) -> (Rect<Val>, Color) {
+    match edges {
+        Some(edges) if !cell.inverse => {
+            let border = cfg.box_border(font_size);
+            let side = |is_edge: bool| Val::Px(if is_edge { border } else { 0.0 });
+            let position = Rect {
+                left: side(edges.left),
+                right: side(edges.right),
+                top: Val::Px(border),
+                bottom: Val::Px(border),
             };
+
+            (position, cfg.background.dimmed(color_factor).into())
         }
+        _ => (Rect::all(Val::Px(0.0)), Color::NONE),
+    }
+}
 
-        // Group together text sections based on their alignment
-        let text_section = TextSection {
-            value: value.clone(),
-            style: TextStyle {
-                font: asset_server.load(font_name),
-                font_size,
-                color,
-            },
-        };
+/// Computes the size of a screen, as wide as a row plus the whitespace used to pad it on the left
+/// side
+pub(super) fn compute_screen_size(font_size: f32, cfg: &ScreenConfig) -> Size<Val> {
+    Size::new(
+        Val::Px(cfg.row_width(font_size) + cfg.font_whitespace(font_size)),
+        Val::Px(cfg.row_height(font_size) * (cfg.rows as f32)),
+    )
+}
 
-        match align {
-            TextAlign::Left => left_text_sections.push(text_section),
-            TextAlign::Center => center_text_sections.push(text_section),
-            TextAlign::Right => right_text_sections.push(text_section),
-        };
+/// Computes the padding of a row, leaving some whitespace before the first character
+pub(super) fn compute_row_padding(font_size: f32, cfg: &ScreenConfig) -> Rect<Val> {
+    Rect {
+        left: Val::Px(cfg.font_whitespace(font_size)),
+        right: Val::Undefined,
+        top: Val::Undefined,
+        bottom: Val::Undefined,
     }
+}
 
-    let create_text_bundle = |align, sections: Vec<TextSection>| -> TextBundle {
-        let left_pos = match align {
-            TextAlign::Left => Val::Px(font_whitespace),
-            TextAlign::Center => {
-                // Compute the horizontal size (in pixels) of the text to be rendered
-                let text_length: usize = sections
-                    .iter()
-                    .map(|s| s.value.graphemes(true).count())
-                    .sum();
-                let text_size = (text_length as f32) * (font_size / FONT_ASPECT_RATIO);
-
-                Val::Px(font_whitespace + (row_width / 2.0) - (text_size / 2.0))
-            }
-            _ => Val::Undefined,
-        };
-        let right_pos = match align {
-            TextAlign::Right => Val::Px(0.0),
-            _ => Val::Undefined,
-        };
-
-        TextBundle {
-            style: Style {
-                position_type: PositionType::Absolute,
-                position: Rect {
-                    left: left_pos,
-                    right: right_pos,
-                    top: Val::Undefined,
-                    bottom: Val::Undefined,
-                },
-                ..default()
-            },
-            text: Text {
-                sections,
-                ..default()
-            },
-            ..default()
-        }
-    };
+/// Computes the size of the background of a single cell
+pub(super) fn compute_cell_size(font_size: f32, cfg: &ScreenConfig) -> Size<Val> {
+    Size::new(
+        Val::Px(cfg.cell_width(font_size)),
+        Val::Px(cfg.row_height(font_size)),
+    )
+}
 
-    if !left_text_sections.is_empty() {
-        text_bundles.push(create_text_bundle(TextAlign::Left, left_text_sections));
-    }
-    if !center_text_sections.is_empty() {
-        text_bundles.push(create_text_bundle(TextAlign::Center, center_text_sections));
+/// Computes the position within the row of the cell at the given column, each cell being as wide
+/// as a single character
+pub(super) fn compute_cell_position(col: usize, font_size: f32, cfg: &ScreenConfig) -> Rect<Val> {
+    Rect {
+        left: Val::Px(cfg.font_whitespace(font_size) + (col as f32) * cfg.cell_width(font_size)),
+        right: Val::Undefined,
+        top: Val::Undefined,
+        bottom: Val::Undefined,
     }
-    if !right_text_sections.is_empty() {
-        text_bundles.push(create_text_bundle(TextAlign::Right, right_text_sections));
-    }
-
-    text_bundles
 }
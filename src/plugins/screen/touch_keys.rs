@@ -0,0 +1,327 @@
+use super::components::{LineSelectKeys, TouchKey, TouchKeyLabel};
+use crate::{
+    config::ScreenConfig,
+    protocol::{McduKey, McduSide, LEFT_LINE_SELECT_KEYS, RIGHT_LINE_SELECT_KEYS},
+};
+use bevy::prelude::*;
+
+/// Width of each column of line select keys, relative to the width of the screen's panel
+pub(super) const LINE_SELECT_KEYS_WIDTH: f32 = 0.08;
+/// Height of the keypad drawn below the screen, relative to the height of the screen's panel
+pub(super) const KEYPAD_HEIGHT: f32 = 0.4;
+/// Height of the labels of the touch keys, relative to the font size of the screen
+pub(super) const LABEL_FONT_SCALE: f32 = 0.6;
+
+pub(super) const KEY_COLOR: Color = Color::rgb(0.15, 0.15, 0.17);
+pub(super) const KEY_HOVERED_COLOR: Color = Color::rgb(0.25, 0.25, 0.28);
+pub(super) const KEY_PRESSED_COLOR: Color = Color::rgb(0.4, 0.4, 0.45);
+
+/// Keys of the keypad drawn below the screen, from the top row to the bottom one
+const KEYPAD_ROWS: [&[McduKey]; 6] = [
+    &[
+        McduKey::Dir,
+        McduKey::Prog,
+        McduKey::Perf,
+        McduKey::Init,
+        McduKey::Data,
+        McduKey::FPln,
+        McduKey::RadNav,
+        McduKey::FuelPred,
+        McduKey::SecFPln,
+        McduKey::AtcComm,
+    ],
+    &[
+        McduKey::McduMenu,
+        McduKey::Airport,
+        McduKey::PrevPage,
+        McduKey::Up,
+        McduKey::NextPage,
+        McduKey::Down,
+        McduKey::Brt,
+        McduKey::Dim,
+        McduKey::Ovfy,
+        McduKey::Clr,
+    ],
+    &[
+        McduKey::Letter('A'),
+        McduKey::Letter('B'),
+        McduKey::Letter('C'),
+        McduKey::Letter('D'),
+        McduKey::Letter('E'),
+        McduKey::Letter('F'),
+        McduKey::Letter('G'),
+        McduKey::Letter('H'),
+        McduKey::Letter('I'),
+        McduKey::Letter('J'),
+    ],
+    &[
+        McduKey::Letter('K'),
+        McduKey::Letter('L'),
+        McduKey::Letter('M'),
+        McduKey::Letter('N'),
+        McduKey::Letter('O'),
+        McduKey::Letter('P'),
+        McduKey::Letter('Q'),
+        McduKey::Letter('R'),
+        McduKey::Letter('S'),
+        McduKey::Letter('T'),
+    ],
+    &[
+        McduKey::Letter('U'),
+        McduKey::Letter('V'),
+        McduKey::Letter('W'),
+        McduKey::Letter('X'),
+        McduKey::Letter('Y'),
+        McduKey::Letter('Z'),
+        McduKey::Slash,
+        McduKey::Space,
+        McduKey::Dot,
+        McduKey::PlusMinus,
+    ],
+    &[
+        McduKey::Digit(1),
+        McduKey::Digit(2),
+        McduKey::Digit(3),
+        McduKey::Digit(4),
+        McduKey::Digit(5),
+        McduKey::Digit(6),
+        McduKey::Digit(7),
+        McduKey::Digit(8),
+        McduKey::Digit(9),
+        McduKey::Digit(0),
+    ],
+];
+
+/// Spawns the panel holding a screen along with its touch keys: the line select keys on both
+/// sides of the screen, aligned to the rows they select, and the keypad below it. Returns the
+/// element the screen has to be added to, between the two columns of line select keys
+pub(super) fn spawn_touch_panel(
+    commands: &mut Commands,
+    container: Entity,
+    side: Option<McduSide>,
+    screen_count: usize,
+    font: &Handle<Font>,
+    font_size: f32,
+    cfg: &ScreenConfig,
+) -> Entity {
+    let panel = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::ColumnReverse,
+                size: Size::new(
+                    Val::Percent(100.0 / screen_count as f32),
+                    Val::Percent(100.0),
+                ),
+                ..default()
+            },
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(Parent(container))
+        .id();
+
+    let screen_row = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                size: Size::new(
+                    Val::Percent(100.0),
+                    Val::Percent(100.0 - KEYPAD_HEIGHT * 100.0),
+                ),
+                ..default()
+            },
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(Parent(panel))
+        .id();
+
+    let keypad = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::ColumnReverse,
+                size: Size::new(Val::Percent(100.0), Val::Percent(KEYPAD_HEIGHT * 100.0)),
+                ..default()
+            },
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(Parent(panel))
+        .id();
+
+    // The children of the row are pushed explicitly, as the screen has to sit between the two
+    // columns of line select keys
+    let left_keys =
+        spawn_line_select_keys(commands, &LEFT_LINE_SELECT_KEYS, side, font, font_size, cfg);
+    let screen_slot = commands
+        .spawn_bundle(NodeBundle {
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .id();
+    let right_keys = spawn_line_select_keys(
+        commands,
+        &RIGHT_LINE_SELECT_KEYS,
+        side,
+        font,
+        font_size,
+        cfg,
+    );
+    commands
+        .entity(screen_row)
+        .push_children(&[left_keys, screen_slot, right_keys]);
+
+    for keys in KEYPAD_ROWS {
+        let keypad_row = commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(
+                        Val::Percent(100.0),
+                        Val::Percent(100.0 / KEYPAD_ROWS.len() as f32),
+                    ),
+                    ..default()
+                },
+                color: UiColor(Color::NONE),
+                ..default()
+            })
+            .insert(Parent(keypad))
+            .id();
+
+        for key in keys {
+            let style = Style {
+                size: Size::new(Val::Percent(100.0 / keys.len() as f32), Val::Auto),
+                margin: Rect::all(Val::Px(2.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            };
+            spawn_touch_key(commands, keypad_row, style, *key, side, font, font_size);
+        }
+    }
+
+    screen_slot
+}
+
+/// Spawns a column of line select keys as tall as the screen, each key covering the row it
+/// selects. Returns the column, to be added next to the screen
+fn spawn_line_select_keys(
+    commands: &mut Commands,
+    keys: &[McduKey],
+    side: Option<McduSide>,
+    font: &Handle<Font>,
+    font_size: f32,
+    cfg: &ScreenConfig,
+) -> Entity {
+    let column = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Relative,
+                size: compute_line_select_keys_size(font_size, cfg),
+                ..default()
+            },
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(LineSelectKeys)
+        .id();
+
+    // Line select keys are aligned to the data rows (2, 4, ..., 12). The y-axis of the UI points
+    // up, so rows are placed from the "bottom" of the layout
+    let row_percent = 100.0 / cfg.rows as f32;
+    for (index, key) in keys.iter().enumerate() {
+        let row_index = (index + 1) * 2;
+        let style = Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                left: Val::Px(2.0),
+                right: Val::Px(2.0),
+                top: Val::Undefined,
+                bottom: Val::Percent(row_index as f32 * row_percent),
+            },
+            size: Size::new(Val::Auto, Val::Percent(row_percent)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        };
+        spawn_touch_key(commands, column, style, *key, side, font, font_size);
+    }
+
+    column
+}
+
+/// Spawns a button pressing the given key, labelled with its name
+fn spawn_touch_key(
+    commands: &mut Commands,
+    parent: Entity,
+    style: Style,
+    key: McduKey,
+    side: Option<McduSide>,
+    font: &Handle<Font>,
+    font_size: f32,
+) {
+    let button = commands
+        .spawn_bundle(ButtonBundle {
+            style,
+            color: UiColor(KEY_COLOR),
+            ..default()
+        })
+        .insert(TouchKey {
+            key,
+            side,
+            is_pressed: false,
+        })
+        .insert(Parent(parent))
+        .id();
+
+    commands
+        .spawn_bundle(TextBundle {
+            text: Text::with_section(
+                compute_key_label(key),
+                TextStyle {
+                    font: font.clone(),
+                    font_size: font_size * LABEL_FONT_SCALE,
+                    color: Color::WHITE,
+                },
+                default(),
+            ),
+            ..default()
+        })
+        .insert(TouchKeyLabel)
+        .insert(Parent(button));
+}
+
+/// Computes the size of a column of line select keys, as tall as the screen
+pub(super) fn compute_line_select_keys_size(font_size: f32, cfg: &ScreenConfig) -> Size<Val> {
+    Size::new(
+        Val::Percent(LINE_SELECT_KEYS_WIDTH * 100.0),
+        Val::Px(cfg.row_height(font_size) * cfg.rows as f32),
+    )
+}
+
+/// Computes the label of a touch key, as printed on the real keypad
+fn compute_key_label(key: McduKey) -> String {
+    match key {
+        McduKey::L1 | McduKey::L2 | McduKey::L3 | McduKey::L4 | McduKey::L5 | McduKey::L6 => {
+            "-".to_string()
+        }
+        McduKey::R1 | McduKey::R2 | McduKey::R3 | McduKey::R4 | McduKey::R5 | McduKey::R6 => {
+            "-".to_string()
+        }
+        McduKey::PrevPage => "←".to_string(),
+        McduKey::NextPage => "→".to_string(),
+        McduKey::Up => "↑".to_string(),
+        McduKey::Down => "↓".to_string(),
+        McduKey::Dot => ".".to_string(),
+        McduKey::Slash => "/".to_string(),
+        McduKey::PlusMinus => "+/-".to_string(),
+        McduKey::Space => "SP".to_string(),
+        McduKey::RadNav => "RAD NAV".to_string(),
+        McduKey::FuelPred => "FUEL PRED".to_string(),
+        McduKey::SecFPln => "SEC F-PLN".to_string(),
+        McduKey::AtcComm => "ATC COMM".to_string(),
+        McduKey::McduMenu => "MCDU MENU".to_string(),
+        McduKey::FPln => "F-PLN".to_string(),
+        _ => key.to_string(),
+    }
+}
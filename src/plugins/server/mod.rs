@@ -1,100 +1,64 @@
 pub mod systems;
 
-use crate::plugins::server::systems::{events_relay, setup};
+use crate::{
+    plugins::server::systems::{
+        events_relay, key_events_relay, request_update_hotkey_system, request_update_relay, setup,
+        switch_side_system,
+    },
+    protocol::{McduKey, McduSide, McduUpdate, ScreenUpdate},
+    server::ServerMessage,
+};
 use bevy::prelude::*;
 use crossbeam_channel::Receiver;
-use serde::Deserialize;
+use tokio::sync::broadcast;
 
-/// Represents an update that has to be drawn on the MCDU screen
-#[derive(Debug)]
-pub struct ScreenUpdate {
-    pub lines: Vec<Vec<ParsedText>>,
-    pub scratchpad: ParsedText,
-    pub title: ParsedText,
-    pub title_left: ParsedText,
-    pub page: ParsedText,
-    pub arrows: Vec<bool>,
-}
+/// Represents the event associated with a key being pressed on the MCDU's keypad
+pub struct McduKeyEvent(pub McduKey);
 
-/// Describes how text should be segmented into sections, each with their owm formatting and
-/// content
-pub type ParsedText = Vec<TextSegment>;
+/// Represents the event associated with a key being held down on the MCDU's keypad long enough
+/// for a long press (e.g. CLR clearing the whole scratchpad)
+pub struct McduKeyHeldEvent(pub McduKey);
 
-#[derive(Debug)]
-pub struct TextSegment {
-    pub formatters: Vec<TextFormatter>,
-    pub value: String,
-}
+/// Represents the event associated with a request to redraw the page currently shown by the MCDU
+pub struct RequestUpdateEvent;
 
-/// Represents the various text formatters that can be used on the MCDU screen
-#[derive(Clone, Debug, Hash, PartialEq)]
-pub enum TextFormatter {
-    AlignLeft,
-    AlignRight,
-    ColorAmber,
-    ColorCyan,
-    ColorGreen,
-    ColorInop,
-    ColorMagenta,
-    ColorRed,
-    ColorWhite,
-    ColorYellow,
-    End,
-    FontBig,
-    FontSmall,
-    Space,
+#[derive(Deref)]
+pub struct ScreenUpdateReceiver(Receiver<ServerMessage>);
+/// Describes the state of the communication with the MCDU
+#[derive(Default)]
+pub struct ServerStatus {
+    /// Why the last screen update received was rejected, if it was
+    pub last_update_error: Option<String>,
 }
-
-impl TextFormatter {
-    pub fn from_str(str: &str) -> Self {
-        match str {
-            "left" => TextFormatter::AlignLeft,
-            "right" => TextFormatter::AlignRight,
-            "amber" => TextFormatter::ColorAmber,
-            "cyan" => TextFormatter::ColorCyan,
-            "green" => TextFormatter::ColorGreen,
-            "inop" => TextFormatter::ColorInop,
-            "magenta" => TextFormatter::ColorMagenta,
-            "red" => TextFormatter::ColorRed,
-            "white" => TextFormatter::ColorWhite,
-            "yellow" => TextFormatter::ColorYellow,
-            "big" => TextFormatter::FontBig,
-            "small" => TextFormatter::FontSmall,
-            "sp" => TextFormatter::Space,
-            _ => TextFormatter::End,
-        }
-    }
+/// Holds the most recent update received from the MCDU, if any
+#[derive(Default)]
+pub struct LatestMcduUpdate(pub Option<McduUpdate>);
+/// Represents the event associated with a screen update request for the given MCDU side
+pub struct ScreenUpdateEvent {
+    pub side: McduSide,
+    pub update: ScreenUpdate,
 }
 
+/// Broadcasts messages from the bevy thread to every client connected to the WebSocket server
 #[derive(Deref)]
-pub struct ScreenUpdateReceiver(Receiver<ScreenUpdate>);
-/// Represents the event associated with a screen update request
-pub struct ScreenUpdateEvent(pub ScreenUpdate);
-
-/// Represents the message sent to the server when a screen update is requested by the client
-#[derive(Debug, Deserialize)]
-struct ScreenUpdateMessage {
-    #[allow(dead_code)]
-    right: ScreenState,
-    left: ScreenState,
-}
-#[derive(Debug, Deserialize)]
-struct ScreenState {
-    lines: Vec<Vec<String>>,
-    scratchpad: String,
-    title: String,
-    #[serde(alias = "titleLeft")]
-    title_left: String,
-    page: String,
-    arrows: Vec<bool>,
-}
+pub struct OutboundMessageSender(broadcast::Sender<String>);
 
 pub struct ServerPlugin;
 
 impl Plugin for ServerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ScreenUpdateEvent>()
+            .add_event::<McduKeyEvent>()
+            .add_event::<McduKeyHeldEvent>()
+            .add_event::<RequestUpdateEvent>()
+            .init_resource::<McduSide>()
+            .init_resource::<LatestMcduUpdate>()
+            .init_resource::<ServerStatus>()
             .add_startup_system(setup)
-            .add_system(events_relay);
+            .add_system(switch_side_system)
+            .add_system(events_relay)
+            .add_system(key_events_relay)
+            .add_system(request_update_hotkey_system)
+            .add_system(request_update_relay);
     }
 }
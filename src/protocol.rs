@@ -0,0 +1,351 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Represents which of the two MCDUs in the cockpit is being replicated
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McduSide {
+    /// The captain's MCDU
+    #[default]
+    #[serde(alias = "captain")]
+    Left,
+    /// The first officer's MCDU
+    #[serde(alias = "first-officer")]
+    Right,
+}
+
+impl McduSide {
+    /// Returns the MCDU on the other side of the cockpit
+    pub fn opposite(self) -> Self {
+        match self {
+            McduSide::Left => McduSide::Right,
+            McduSide::Right => McduSide::Left,
+        }
+    }
+}
+
+impl fmt::Display for McduSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            McduSide::Left => write!(f, "left"),
+            McduSide::Right => write!(f, "right"),
+        }
+    }
+}
+
+impl FromStr for McduSide {
+    type Err = String;
+
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        match str {
+            "left" | "captain" | "capt" => Ok(McduSide::Left),
+            "right" | "first-officer" | "fo" => Ok(McduSide::Right),
+            _ => Err(format!("unknown MCDU side \"{}\"", str)),
+        }
+    }
+}
+
+/// Represents the updates sent for both the captain's and first officer's MCDUs
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct McduUpdate {
+    pub left: ScreenUpdate,
+    pub right: ScreenUpdate,
+}
+
+impl McduUpdate {
+    /// Returns the update that has to be drawn on the given side's MCDU screen
+    pub fn side(&self, side: McduSide) -> &ScreenUpdate {
+        match side {
+            McduSide::Left => &self.left,
+            McduSide::Right => &self.right,
+        }
+    }
+}
+
+// Describe the content of a screen update: 12 lines (labels and data) of 3 columns each, and the
+// up, down, left and right scroll arrows
+pub const SCREEN_LINES: usize = 12;
+pub const LINE_COLUMNS: usize = 3;
+pub const SCREEN_ARROWS: usize = 4;
+
+/// Represents an update that has to be drawn on the MCDU screen. Always made of exactly
+/// SCREEN_LINES lines of LINE_COLUMNS columns each, and SCREEN_ARROWS arrows
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ScreenUpdate {
+    pub lines: Vec<Vec<ParsedText>>,
+    pub scratchpad: ParsedText,
+    pub title: ParsedText,
+    pub title_left: ParsedText,
+    pub page: ParsedText,
+    pub arrows: Vec<bool>,
+}
+
+impl Default for ScreenUpdate {
+    /// Returns a blank screen, as shown before the MCDU sends its first update
+    fn default() -> Self {
+        Self {
+            lines: vec![vec![ParsedText::new(); LINE_COLUMNS]; SCREEN_LINES],
+            scratchpad: ParsedText::new(),
+            title: ParsedText::new(),
+            title_left: ParsedText::new(),
+            page: ParsedText::new(),
+            arrows: vec![false; SCREEN_ARROWS],
+        }
+    }
+}
+
+/// Describes how text should be segmented into sections, each with their owm formatting and
+/// content
+pub type ParsedText = Vec<TextSegment>;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TextSegment {
+    pub formatters: Vec<TextFormatter>,
+    pub value: String,
+}
+
+/// Represents the various text formatters that can be used on the MCDU screen
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize)]
+pub enum TextFormatter {
+    AlignLeft,
+    AlignRight,
+    ColorAmber,
+    ColorCyan,
+    ColorGreen,
+    ColorInop,
+    ColorMagenta,
+    ColorRed,
+    ColorWhite,
+    ColorYellow,
+    FontBig,
+    FontSmall,
+    /// Draws the text in the background color over a block of its own color (e.g. selected
+    /// fields)
+    Inverse,
+    /// Draws a box around the text (e.g. mandatory fields)
+    Boxed,
+    /// Makes the text flash (e.g. scratchpad messages and pending prompts)
+    Flash,
+}
+
+impl TextFormatter {
+    /// Returns the formatter opened by the given tag (e.g. "amber" for `{amber}`), if any
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "left" => Some(TextFormatter::AlignLeft),
+            "right" => Some(TextFormatter::AlignRight),
+            "amber" => Some(TextFormatter::ColorAmber),
+            "cyan" => Some(TextFormatter::ColorCyan),
+            "green" => Some(TextFormatter::ColorGreen),
+            "inop" => Some(TextFormatter::ColorInop),
+            "magenta" => Some(TextFormatter::ColorMagenta),
+            "red" => Some(TextFormatter::ColorRed),
+            "white" => Some(TextFormatter::ColorWhite),
+            "yellow" => Some(TextFormatter::ColorYellow),
+            "big" => Some(TextFormatter::FontBig),
+            "small" => Some(TextFormatter::FontSmall),
+            "inverse" => Some(TextFormatter::Inverse),
+            "box" => Some(TextFormatter::Boxed),
+            "flash" => Some(TextFormatter::Flash),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the tag opening the formatter
+    pub fn tag(self) -> &'static str {
+        match self {
+            TextFormatter::AlignLeft => "left",
+            TextFormatter::AlignRight => "right",
+            TextFormatter::ColorAmber => "amber",
+            TextFormatter::ColorCyan => "cyan",
+            TextFormatter::ColorGreen => "green",
+            TextFormatter::ColorInop => "inop",
+            TextFormatter::ColorMagenta => "magenta",
+            TextFormatter::ColorRed => "red",
+            TextFormatter::ColorWhite => "white",
+            TextFormatter::ColorYellow => "yellow",
+            TextFormatter::FontBig => "big",
+            TextFormatter::FontSmall => "small",
+            TextFormatter::Inverse => "inverse",
+            TextFormatter::Boxed => "box",
+            TextFormatter::Flash => "flash",
+        }
+    }
+}
+
+/// Represents a key on the MCDU's keypad
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum McduKey {
+    L1,
+    L2,
+    L3,
+    L4,
+    L5,
+    L6,
+    R1,
+    R2,
+    R3,
+    R4,
+    R5,
+    R6,
+    Dir,
+    Prog,
+    Perf,
+    Init,
+    Data,
+    FPln,
+    RadNav,
+    FuelPred,
+    SecFPln,
+    AtcComm,
+    McduMenu,
+    Airport,
+    PrevPage,
+    NextPage,
+    Up,
+    Down,
+    Letter(char),
+    Digit(u8),
+    Dot,
+    Slash,
+    PlusMinus,
+    Space,
+    Ovfy,
+    Clr,
+    /// Makes the screen brighter, handled by the replica itself
+    Brt,
+    /// Makes the screen dimmer, handled by the replica itself
+    Dim,
+}
+
+/// Line select keys on the left of the screen, from the top to the bottom
+pub const LEFT_LINE_SELECT_KEYS: [McduKey; 6] = [
+    McduKey::L1,
+    McduKey::L2,
+    McduKey::L3,
+    McduKey::L4,
+    McduKey::L5,
+    McduKey::L6,
+];
+/// Line select keys on the right of the screen, from the top to the bottom
+pub const RIGHT_LINE_SELECT_KEYS: [McduKey; 6] = [
+    McduKey::R1,
+    McduKey::R2,
+    McduKey::R3,
+    McduKey::R4,
+    McduKey::R5,
+    McduKey::R6,
+];
+
+impl fmt::Display for McduKey {
+    /// Formats the key using the name expected by the A32NX remote MCDU
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            McduKey::L1 => write!(f, "L1"),
+            McduKey::L2 => write!(f, "L2"),
+            McduKey::L3 => write!(f, "L3"),
+            McduKey::L4 => write!(f, "L4"),
+            McduKey::L5 => write!(f, "L5"),
+            McduKey::L6 => write!(f, "L6"),
+            McduKey::R1 => write!(f, "R1"),
+            McduKey::R2 => write!(f, "R2"),
+            McduKey::R3 => write!(f, "R3"),
+            McduKey::R4 => write!(f, "R4"),
+            McduKey::R5 => write!(f, "R5"),
+            McduKey::R6 => write!(f, "R6"),
+            McduKey::Dir => write!(f, "DIR"),
+            McduKey::Prog => write!(f, "PROG"),
+            McduKey::Perf => write!(f, "PERF"),
+            McduKey::Init => write!(f, "INIT"),
+            McduKey::Data => write!(f, "DATA"),
+            McduKey::FPln => write!(f, "FPLN"),
+            McduKey::RadNav => write!(f, "RAD"),
+            McduKey::FuelPred => write!(f, "FUEL"),
+            McduKey::SecFPln => write!(f, "SEC"),
+            McduKey::AtcComm => write!(f, "ATC"),
+            McduKey::McduMenu => write!(f, "MENU"),
+            McduKey::Airport => write!(f, "AIRPORT"),
+            McduKey::PrevPage => write!(f, "PREVPAGE"),
+            McduKey::NextPage => write!(f, "NEXTPAGE"),
+            McduKey::Up => write!(f, "UP"),
+            McduKey::Down => write!(f, "DOWN"),
+            McduKey::Letter(letter) => write!(f, "{}", letter.to_ascii_uppercase()),
+            McduKey::Digit(digit) => write!(f, "{}", digit),
+            McduKey::Dot => write!(f, "DOT"),
+            McduKey::Slash => write!(f, "DIV"),
+            McduKey::PlusMinus => write!(f, "PLUSMINUS"),
+            McduKey::Space => write!(f, "SP"),
+            McduKey::Ovfy => write!(f, "OVFY"),
+            McduKey::Clr => write!(f, "CLR"),
+            McduKey::Brt => write!(f, "BRT"),
+            McduKey::Dim => write!(f, "DIM"),
+        }
+    }
+}
+
+impl FromStr for McduKey {
+    type Err = String;
+
+    /// Parses the name of a key, as expected by the A32NX remote MCDU
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        let key = match str {
+            "L1" => McduKey::L1,
+            "L2" => McduKey::L2,
+            "L3" => McduKey::L3,
+            "L4" => McduKey::L4,
+            "L5" => McduKey::L5,
+            "L6" => McduKey::L6,
+            "R1" => McduKey::R1,
+            "R2" => McduKey::R2,
+            "R3" => McduKey::R3,
+            "R4" => McduKey::R4,
+            "R5" => McduKey::R5,
+            "R6" => McduKey::R6,
+            "DIR" => McduKey::Dir,
+            "PROG" => McduKey::Prog,
+            "PERF" => McduKey::Perf,
+            "INIT" => McduKey::Init,
+            "DATA" => McduKey::Data,
+            "FPLN" => McduKey::FPln,
+            "RAD" => McduKey::RadNav,
+            "FUEL" => McduKey::FuelPred,
+            "SEC" => McduKey::SecFPln,
+            "ATC" => McduKey::AtcComm,
+            "MENU" => McduKey::McduMenu,
+            "AIRPORT" => McduKey::Airport,
+            "PREVPAGE" => McduKey::PrevPage,
+            "NEXTPAGE" => McduKey::NextPage,
+            "UP" => McduKey::Up,
+            "DOWN" => McduKey::Down,
+            "DOT" => McduKey::Dot,
+            "DIV" => McduKey::Slash,
+            "PLUSMINUS" => McduKey::PlusMinus,
+            "SP" => McduKey::Space,
+            "OVFY" => McduKey::Ovfy,
+            "CLR" => McduKey::Clr,
+            "BRT" => McduKey::Brt,
+            "DIM" => McduKey::Dim,
+            _ => {
+                let mut chars = str.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(char), None) if char.is_ascii_uppercase() => McduKey::Letter(char),
+                    (Some(char), None) if char.is_ascii_digit() => {
+                        McduKey::Digit(char as u8 - b'0')
+                    }
+                    _ => return Err(format!("unknown MCDU key \"{}\"", str)),
+                }
+            }
+        };
+
+        Ok(key)
+    }
+}
+
+impl<'de> Deserialize<'de> for McduKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.to_ascii_uppercase()
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
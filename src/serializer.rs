@@ -0,0 +1,151 @@
+use crate::{
+    parser::{ScreenState, ScreenUpdateMessage, Token},
+    protocol::{McduUpdate, ParsedText, ScreenUpdate},
+};
+
+/// Writes text segments back as the formatter markup used by the FlyByWire's A32NX mod, closing
+/// every formatter opened. Parsing the markup gives back the same segments, as long as the text
+/// does not contain tags itself
+pub fn serialize_text(parsed_text: &ParsedText) -> String {
+    let mut tokens = Vec::new();
+    let mut formatters_stack = Vec::new();
+
+    for segment in parsed_text {
+        // Close the formatters not shared with the segment, then open the missing ones
+        let shared = formatters_stack
+            .iter()
+            .zip(&segment.formatters)
+            .take_while(|(open, needed)| open == needed)
+            .count();
+        while formatters_stack.len() > shared {
+            formatters_stack.pop();
+            tokens.push(Token::End);
+        }
+        for formatter in &segment.formatters[shared..] {
+            formatters_stack.push(*formatter);
+            tokens.push(Token::Open(*formatter));
+        }
+
+        tokens.push(Token::Text(&segment.value));
+    }
+    tokens.extend(formatters_stack.iter().map(|_| Token::End));
+
+    tokens.iter().map(Token::to_string).collect()
+}
+
+/// Builds the "update" command the MCDU sends to draw the given update on both sides
+pub fn serialize_update(update: &McduUpdate) -> String {
+    let msg = ScreenUpdateMessage {
+        right: build_screen_state(&update.right),
+        left: build_screen_state(&update.left),
+    };
+
+    // Only made of strings and booleans, the message can always be serialized
+    format!("update:{}", serde_json::to_string(&msg).unwrap())
+}
+
+/// Builds the raw state of a single MCDU, the reverse of the parser's `build_screen_update`
+fn build_screen_state(screen_update: &ScreenUpdate) -> ScreenState {
+    ScreenState {
+        lines: screen_update
+            .lines
+            .iter()
+            .map(|line| {
+                // Swap the center and right column back to the layout used by the A32NX mod
+                let mut line = line.iter().map(serialize_text).collect::<Vec<_>>();
+                line.swap(1, 2);
+
+                line
+            })
+            .collect(),
+        scratchpad: serialize_text(&screen_update.scratchpad),
+        title: serialize_text(&screen_update.title),
+        title_left: serialize_text(&screen_update.title_left),
+        page: serialize_text(&screen_update.page),
+        arrows: screen_update.arrows.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parser::{parse_raw_text, parse_update},
+        protocol::{TextFormatter, TextSegment},
+    };
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+    use std::fs;
+
+    const FORMATTERS: [TextFormatter; 15] = [
+        TextFormatter::AlignLeft,
+        TextFormatter::AlignRight,
+        TextFormatter::ColorAmber,
+        TextFormatter::ColorCyan,
+        TextFormatter::ColorGreen,
+        TextFormatter::ColorInop,
+        TextFormatter::ColorMagenta,
+        TextFormatter::ColorRed,
+        TextFormatter::ColorWhite,
+        TextFormatter::ColorYellow,
+        TextFormatter::FontBig,
+        TextFormatter::FontSmall,
+        TextFormatter::Inverse,
+        TextFormatter::Boxed,
+        TextFormatter::Flash,
+    ];
+
+    // Tags are made of lowercase letters, so text built from these characters never contains one
+    const CHARACTERS: &[char] = &[
+        'A', 'Z', '0', '9', ' ', '/', '-', '.', '°', '←', '{', '}', '[', ']',
+    ];
+
+    /// Generates text segments as the parser would produce them: no empty segments, and no
+    /// adjacent segments with the same formatters
+    fn random_parsed_text(rng: &mut StdRng) -> ParsedText {
+        let mut parsed_text: ParsedText = Vec::new();
+
+        for _ in 0..rng.gen_range(0..6) {
+            let formatters = (0..rng.gen_range(0..4))
+                .map(|_| *FORMATTERS.choose(rng).unwrap())
+                .collect::<Vec<_>>();
+            let value = (0..rng.gen_range(1..8))
+                .map(|_| *CHARACTERS.choose(rng).unwrap())
+                .collect::<String>();
+
+            match parsed_text.last_mut() {
+                Some(segment) if segment.formatters == formatters => segment.value += &value,
+                _ => parsed_text.push(TextSegment { formatters, value }),
+            }
+        }
+
+        parsed_text
+    }
+
+    #[test]
+    fn random_text_round_trips() {
+        let mut rng = StdRng::seed_from_u64(0xa32);
+
+        for _ in 0..2000 {
+            let parsed_text = random_parsed_text(&mut rng);
+            let markup = serialize_text(&parsed_text);
+            assert_eq!(parse_raw_text(&markup), parsed_text, "{}", markup);
+        }
+    }
+
+    #[test]
+    fn formatters_are_opened_and_closed_as_needed() {
+        let markup = "{small}{white}---{end}{green}/ -{end}{end}FL280";
+        assert_eq!(serialize_text(&parse_raw_text(markup)), markup);
+        assert_eq!(serialize_text(&Vec::new()), "");
+    }
+
+    #[test]
+    fn test_message_round_trips() {
+        let json_msg = fs::read_to_string("test_message.json").unwrap();
+        let mcdu_update = parse_update(&json_msg).unwrap();
+
+        let msg = serialize_update(&mcdu_update);
+        let data = msg.strip_prefix("update:").unwrap();
+        assert_eq!(parse_update(data).unwrap(), mcdu_update);
+    }
+}
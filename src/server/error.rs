@@ -0,0 +1,46 @@
+use std::{fmt, io};
+use tokio_tungstenite::tungstenite;
+
+/// Represents the errors that can occur while communicating with the MCDU
+#[derive(Debug)]
+pub enum ServerError {
+    /// The WebSocket server could not listen on the given address
+    Bind(String, io::Error),
+    /// A new TCP connection could not be accepted
+    Accept(io::Error),
+    /// The WebSocket handshake with the other end failed
+    Handshake(Box<tungstenite::Error>),
+    /// The WebSocket connection failed after being established
+    Connection(Box<tungstenite::Error>),
+    /// An "update" command was received without any data
+    MissingUpdateData,
+    /// The data of an "update" command is not a valid screen update
+    InvalidUpdate(serde_json::Error),
+    /// The bevy thread is no longer receiving updates
+    ChannelClosed,
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerError::Bind(addr, err) => write!(f, "cannot listen on {}: {}", addr, err),
+            ServerError::Accept(err) => write!(f, "cannot accept connection: {}", err),
+            ServerError::Handshake(err) => write!(f, "WebSocket handshake failed: {}", err),
+            ServerError::Connection(err) => write!(f, "WebSocket connection error: {}", err),
+            ServerError::MissingUpdateData => write!(f, "missing update data"),
+            ServerError::InvalidUpdate(err) => write!(f, "invalid update data: {}", err),
+            ServerError::ChannelClosed => write!(f, "screen update channel closed"),
+        }
+    }
+}
+
+impl std::error::Error for ServerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ServerError::Bind(_, err) | ServerError::Accept(err) => Some(err),
+            ServerError::Handshake(err) | ServerError::Connection(err) => Some(err.as_ref()),
+            ServerError::InvalidUpdate(err) => Some(err),
+            ServerError::MissingUpdateData | ServerError::ChannelClosed => None,
+        }
+    }
+}
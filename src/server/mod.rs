@@ -0,0 +1,425 @@
+pub mod error;
+
+use self::error::ServerError;
+use crate::{config::ConnectionConfig, parser::parse_update, protocol::McduUpdate};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use futures_util::{future, SinkExt, StreamExt, TryStreamExt};
+use std::{collections::VecDeque, fs, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+    runtime::Builder,
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc,
+    },
+    time::sleep,
+};
+use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
+use tracing::{error, info, warn};
+
+const OUTBOUND_CHANNEL_CAPACITY: usize = 64;
+const RECONNECT_MIN_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+pub const MCDU_CONNECTED_MSG: &str = "mcduConnected";
+pub const REQUEST_UPDATE_MSG: &str = "requestUpdate";
+
+/// Describes how the connection with the MCDU is established
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionMode {
+    /// Listens for the MCDU to connect to the WebSocket server
+    Server,
+    /// Connects to the MCDU server at the given WebSocket endpoint (e.g. `ws://host:port`)
+    Client(String),
+}
+
+/// Represents the messages relayed from the MCDU by the WebSocket runtime
+#[derive(Debug)]
+pub enum ServerMessage {
+    /// A screen update was received for both MCDU sides
+    Update(Box<McduUpdate>),
+    /// The last screen update received could not be processed
+    UpdateRejected(ServerError),
+}
+
+/// Starts communicating with the MCDU on a different thread. Returns the receiving end of the
+/// messages relayed from the MCDU and the sender used to send messages to it
+pub fn start_connection(
+    config: &ConnectionConfig,
+) -> (Receiver<ServerMessage>, broadcast::Sender<String>) {
+    let connection_mode = config.mode();
+    let bind_addr = config.bind.clone();
+    let (tx, rx) = unbounded::<ServerMessage>();
+    let (outbound_tx, _) = broadcast::channel::<String>(OUTBOUND_CHANNEL_CAPACITY);
+    let server_outbound_tx = outbound_tx.clone();
+
+    std::thread::spawn(move || {
+        if cfg!(feature = "debug-test-msg") {
+            // Loads a test message from a local JSON file
+            let path = "test_message.json";
+            match fs::read_to_string(path) {
+                Ok(json_msg) => {
+                    relay_update(&tx, handle_update_command(Some(&json_msg)));
+                    info!("Test message loaded");
+                }
+                Err(err) => error!("Failed to load {}: {}", path, err),
+            }
+            return;
+        }
+
+        // Start the WebSocket server (or client) on a different thread
+        let runtime = Builder::new_current_thread()
+            .enable_io()
+            .enable_time()
+            .build()
+            .unwrap();
+
+        match connection_mode {
+            ConnectionMode::Server => {
+                runtime.block_on(ws_server_runtime(bind_addr, tx, server_outbound_tx))
+            }
+            ConnectionMode::Client(url) => {
+                runtime.block_on(ws_client_runtime(url, tx, server_outbound_tx))
+            }
+        }
+    });
+
+    (rx, outbound_tx)
+}
+
+/// Set-ups the WebSocket server used to communicate with the MCDU
+async fn ws_server_runtime(
+    bind_addr: String,
+    tx: Sender<ServerMessage>,
+    outbound_tx: broadcast::Sender<String>,
+) {
+    // Create the TCP listener, retrying in case the address is still in use
+    let mut retry_delay = RECONNECT_MIN_DELAY;
+    let listener = loop {
+        match TcpListener::bind(&bind_addr).await {
+            Ok(listener) => break listener,
+            Err(err) => {
+                error!("{}", ServerError::Bind(bind_addr.clone(), err));
+                sleep(retry_delay).await;
+                retry_delay = (retry_delay * 2).min(RECONNECT_MAX_DELAY);
+            }
+        }
+    };
+    info!("Listening on {}", bind_addr);
+
+    // Event loop that will accept connections, a failed connection must not stop the server
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(handle_connection(
+                    stream,
+                    tx.clone(),
+                    outbound_tx.subscribe(),
+                ));
+            }
+            Err(err) => {
+                warn!("{}", ServerError::Accept(err));
+                sleep(RECONNECT_MIN_DELAY).await;
+            }
+        }
+    }
+}
+
+/// Set-ups the WebSocket client used to communicate with the MCDU server, reconnecting with an
+/// exponential backoff whenever the connection fails or drops
+async fn ws_client_runtime(
+    url: String,
+    tx: Sender<ServerMessage>,
+    outbound_tx: broadcast::Sender<String>,
+) {
+    let mut reconnect_delay = RECONNECT_MIN_DELAY;
+
+    loop {
+        info!("Connecting to {}", url);
+        match tokio_tungstenite::connect_async(url.as_str()).await {
+            Ok((ws_stream, _)) => {
+                info!("Connected to {}", url);
+                reconnect_delay = RECONNECT_MIN_DELAY;
+
+                // Announce ourselves and ask for the current page so the screen populates
+                // immediately
+                let greeting = [MCDU_CONNECTED_MSG, REQUEST_UPDATE_MSG];
+                handle_ws_stream(ws_stream, &greeting, tx.clone(), outbound_tx.subscribe()).await;
+                info!("Connection to {} closed", url);
+            }
+            Err(err) => warn!(
+                "Failed to connect to {}: {}",
+                url,
+                ServerError::Handshake(Box::new(err))
+            ),
+        }
+
+        info!("Reconnecting in {:?}", reconnect_delay);
+        sleep(reconnect_delay).await;
+        reconnect_delay = (reconnect_delay * 2).min(RECONNECT_MAX_DELAY);
+    }
+}
+
+/// Accepts a new WebSocket connection and handles the client/server communication
+async fn handle_connection(
+    stream: TcpStream,
+    tx: Sender<ServerMessage>,
+    outbound_rx: broadcast::Receiver<String>,
+) {
+    // Accept a new WebSocket connection
+    let remote_addr = match stream.peer_addr() {
+        Ok(remote_addr) => remote_addr.to_string(),
+        Err(_) => "unknown address".to_string(),
+    };
+    let ws_stream = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws_stream) => ws_stream,
+        Err(err) => {
+            warn!(
+                "{} ({})",
+                ServerError::Handshake(Box::new(err)),
+                remote_addr
+            );
+            return;
+        }
+    };
+    info!("New WebSocket connection from {}", remote_addr);
+
+    // Ask for the current page straight away instead of waiting for the MCDU to push one
+    handle_ws_stream(ws_stream, &[REQUEST_UPDATE_MSG], tx, outbound_rx).await;
+    info!("WebSocket connection from {} closed", remote_addr);
+}
+
+/// Handles the messages exchanged over an established WebSocket connection until it gets closed,
+/// starting with the given greeting messages
+async fn handle_ws_stream<S>(
+    mut ws_stream: WebSocketStream<S>,
+    greeting: &[&str],
+    tx: Sender<ServerMessage>,
+    mut outbound_rx: broadcast::Receiver<String>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    for msg in greeting {
+        if let Err(err) = ws_stream.send(Message::Text(msg.to_string())).await {
+            warn!("Failed to send {:?}: {}", msg, err);
+            return;
+        }
+    }
+
+    let (mut write, read) = ws_stream.split();
+    let (reply_tx, mut reply_rx) = mpsc::unbounded_channel::<String>();
+
+    // Handle outgoing messages, either replies to this connection or broadcasts to all of them
+    let handle_outbound = async move {
+        loop {
+            let msg = tokio::select! {
+                Some(reply) = reply_rx.recv() => reply,
+                broadcast = outbound_rx.recv() => match broadcast {
+                    Ok(msg) => msg,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Dropped {} outgoing messages", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            };
+
+            if write.send(Message::Text(msg)).await.is_err() {
+                break;
+            }
+        }
+    };
+
+    // Handle incoming messages
+    let handle_inbound = read.try_for_each(|ws_message| {
+        if let Message::Text(msg) = ws_message {
+            // Extract the command and (optional) data from the message sent by the MCDU
+            let mut sections = msg.splitn(2, ":").collect::<VecDeque<&str>>();
+            let (_command, data) = (sections.pop_front(), sections.pop_back());
+
+            // Handle commands
+            if let Some(command) = _command {
+                info!("MCDU message: {:?}", command);
+                match command {
+                    "update" => relay_update(&tx, handle_update_command(data)),
+                    "mcduConnected" => {
+                        // The MCDU (re)connected on its side, fetch the page it is showing
+                        let _ = reply_tx.send(REQUEST_UPDATE_MSG.to_string());
+                    }
+                    _ => {}
+                };
+            }
+        }
+
+        future::ready(Ok(()))
+    });
+
+    tokio::select! {
+        result = handle_inbound => {
+            if let Err(err) = result {
+                warn!("{}", ServerError::Connection(Box::new(err)));
+            }
+        }
+        _ = handle_outbound => {}
+    };
+}
+
+/// Sends the outcome of an "update" command to the frontend. Rejected updates are logged and
+/// skipped, leaving the screen as it was
+fn relay_update(tx: &Sender<ServerMessage>, update: Result<McduUpdate, ServerError>) {
+    let server_message = match update {
+        Ok(mcdu_update) => ServerMessage::Update(Box::new(mcdu_update)),
+        Err(err) => {
+            warn!("Update rejected: {}", err);
+            ServerMessage::UpdateRejected(err)
+        }
+    };
+
+    if tx.send(server_message).is_err() {
+        error!("{}", ServerError::ChannelClosed);
+    }
+}
+
+/// Handles the "update" command sent by the MCDU
+fn handle_update_command(data: Option<&str>) -> Result<McduUpdate, ServerError> {
+    let data = data.ok_or(ServerError::MissingUpdateData)?;
+    parse_update(data).map_err(ServerError::InvalidUpdate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    const TEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Waits for the next message sent by the other end of the WebSocket connection
+    async fn next_text_msg<S>(ws_stream: &mut WebSocketStream<S>) -> String
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        match timeout(TEST_TIMEOUT, ws_stream.next()).await {
+            Ok(Some(Ok(Message::Text(msg)))) => msg,
+            other => panic!("Expected a text message, got {:?}", other),
+        }
+    }
+
+    /// Waits for the next message relayed by the WebSocket runtime
+    async fn next_server_message(rx: &Receiver<ServerMessage>) -> ServerMessage {
+        timeout(TEST_TIMEOUT, async {
+            loop {
+                if let Ok(server_message) = rx.try_recv() {
+                    return server_message;
+                }
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("No message received")
+    }
+
+    #[tokio::test]
+    async fn client_greets_relays_and_reconnects() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let json_msg = fs::read_to_string("test_message.json").unwrap();
+
+        let (tx, rx) = unbounded::<ServerMessage>();
+        let (outbound_tx, _) = broadcast::channel::<String>(OUTBOUND_CHANNEL_CAPACITY);
+        let client = tokio::spawn(ws_client_runtime(url, tx, outbound_tx.clone()));
+
+        // Drop the connection once to make sure the client dials the server again
+        for _ in 0..2 {
+            let (stream, _) = timeout(TEST_TIMEOUT, listener.accept())
+                .await
+                .unwrap()
+                .unwrap();
+            let mut ws_stream = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+            assert_eq!(next_text_msg(&mut ws_stream).await, "mcduConnected");
+            assert_eq!(next_text_msg(&mut ws_stream).await, "requestUpdate");
+
+            ws_stream
+                .send(Message::Text(format!("update:{}", json_msg)))
+                .await
+                .unwrap();
+            match next_server_message(&rx).await {
+                ServerMessage::Update(mcdu_update) => assert_eq!(mcdu_update.left.lines.len(), 12),
+                other => panic!("Expected an update, got {:?}", other),
+            }
+
+            outbound_tx.send("event:left:L1".to_string()).unwrap();
+            assert_eq!(next_text_msg(&mut ws_stream).await, "event:left:L1");
+
+            ws_stream.close(None).await.unwrap();
+        }
+
+        client.abort();
+    }
+
+    #[tokio::test]
+    async fn server_requests_update_on_connect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let (tx, _rx) = unbounded::<ServerMessage>();
+        let (outbound_tx, _) = broadcast::channel::<String>(OUTBOUND_CHANNEL_CAPACITY);
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            handle_connection(stream, tx, outbound_tx.subscribe()).await;
+        });
+
+        // Stand-in MCDU connecting to the server
+        let (mut ws_stream, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        assert_eq!(next_text_msg(&mut ws_stream).await, "requestUpdate");
+
+        ws_stream
+            .send(Message::Text("mcduConnected".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(next_text_msg(&mut ws_stream).await, "requestUpdate");
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn malformed_update_is_rejected_without_dropping_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let json_msg = fs::read_to_string("test_message.json").unwrap();
+
+        let (tx, rx) = unbounded::<ServerMessage>();
+        let (outbound_tx, _) = broadcast::channel::<String>(OUTBOUND_CHANNEL_CAPACITY);
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            handle_connection(stream, tx, outbound_tx.subscribe()).await;
+        });
+
+        let (mut ws_stream, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        for msg in [
+            "update",
+            "update:{\"left\": 42}",
+            &format!("update:{}", json_msg),
+        ] {
+            ws_stream
+                .send(Message::Text(msg.to_string()))
+                .await
+                .unwrap();
+        }
+
+        assert!(matches!(
+            next_server_message(&rx).await,
+            ServerMessage::UpdateRejected(ServerError::MissingUpdateData)
+        ));
+        assert!(matches!(
+            next_server_message(&rx).await,
+            ServerMessage::UpdateRejected(ServerError::InvalidUpdate(_))
+        ));
+        assert!(matches!(
+            next_server_message(&rx).await,
+            ServerMessage::Update(_)
+        ));
+
+        server.abort();
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    config::{Config, ScreenConfig, ScreenMode},
+    grid::{Cell, CellColor, CellSize, Grid},
+    protocol::{McduSide, McduUpdate, ScreenUpdate},
+    server::{start_connection, ServerMessage},
+};
+use std::io::{self, Write};
+
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+const RESET_STYLE: &str = "\x1b[0m";
+
+/// Draws the MCDU screens in the terminal, updating them as the MCDU sends new pages. Runs until
+/// the connection with the MCDU is closed or the process is interrupted
+pub fn run(config: &Config) -> io::Result<()> {
+    let (rx, _outbound_tx) = start_connection(&config.connection);
+    let cfg = &config.screen;
+
+    let sides = match config.mcdu.mode {
+        ScreenMode::Single => vec![config.mcdu.side],
+        ScreenMode::Dual => vec![McduSide::Left, McduSide::Right],
+    };
+
+    let mut latest_update: Option<McduUpdate> = None;
+    let mut status = "Waiting for the MCDU to connect".to_string();
+    draw(&sides, latest_update.as_ref(), &status, cfg)?;
+
+    for server_message in rx.iter() {
+        match server_message {
+            ServerMessage::Update(mcdu_update) => {
+                latest_update = Some(*mcdu_update);
+                status.clear();
+            }
+            ServerMessage::UpdateRejected(err) => {
+                status = format!("Last update rejected: {}", err);
+            }
+        }
+
+        draw(&sides, latest_update.as_ref(), &status, cfg)?;
+    }
+
+    Ok(())
+}
+
+/// Redraws the whole terminal, showing the screens of the given sides next to each other
+fn draw(
+    sides: &[McduSide],
+    update: Option<&McduUpdate>,
+    status: &str,
+    cfg: &ScreenConfig,
+) -> io::Result<()> {
+    let blank_update = ScreenUpdate::default();
+    let screens = sides
+        .iter()
+        .map(|side| {
+            let update = update.map_or(&blank_update, |update| update.side(*side));
+            render_screen(&Grid::from_update(update, cfg.rows, cfg.cols))
+        })
+        .collect::<Vec<_>>();
+
+    let mut frame = String::from(CLEAR_SCREEN);
+    for line_index in 0..screens[0].len() {
+        let line = screens
+            .iter()
+            .map(|screen| screen[line_index].as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        frame.push_str(&line);
+        frame.push('\n');
+    }
+    frame.push_str(status);
+    frame.push('\n');
+
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(frame.as_bytes())?;
+    stdout.flush()
+}
+
+/// Renders a grid of cells to the lines of text drawing it in the terminal, framed by a border
+pub fn render_screen(grid: &Grid) -> Vec<String> {
+    let cols = grid.row(0).len();
+    let border = "─".repeat(cols);
+
+    let mut lines = vec![format!("┌{}┐", border)];
+    lines.extend(grid.rows().map(|row| format!("│{}│", render_row(row))));
+    lines.push(format!("└{}┘", border));
+
+    lines
+}
+
+/// Renders the cells of a row, changing the terminal style only between cells drawn differently
+fn render_row(row: &[Cell]) -> String {
+    let mut line = String::new();
+    let mut current_style = None;
+
+    for cell in row {
+        let style = compute_cell_style(cell);
+        if current_style.as_ref() != Some(&style) {
+            line.push_str(&style);
+            current_style = Some(style);
+        }
+        line.push(cell.char);
+    }
+    line.push_str(RESET_STYLE);
+
+    line
+}
+
+/// Computes the ANSI escape sequence drawing the text of a cell. Small characters are dimmed,
+/// inverse characters swap the text and background colors, boxed characters are underlined (the
+/// closest a terminal gets to a box) and flashing characters blink
+fn compute_cell_style(cell: &Cell) -> String {
+    let color = match cell.color {
+        CellColor::Amber => "38;5;214",
+        CellColor::Cyan => "36",
+        CellColor::Green => "32",
+        CellColor::Inop => "90",
+        CellColor::Magenta => "35",
+        CellColor::Red => "31",
+        CellColor::White => "37",
+        CellColor::Yellow => "33",
+    };
+
+    let mut style = String::from("\x1b[0;");
+    if cell.size == CellSize::Small {
+        style.push_str("2;");
+    }
+    if cell.inverse {
+        style.push_str("7;");
+    }
+    if cell.boxed {
+        style.push_str("4;");
+    }
+    if cell.flashing {
+        style.push_str("5;");
+    }
+    style.push_str(color);
+    style.push('m');
+
+    style
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{TextFormatter, TextSegment};
+
+    #[test]
+    fn cells_are_drawn_with_ansi_styles() {
+        let update = ScreenUpdate {
+            scratchpad: vec![
+                TextSegment {
+                    formatters: vec![TextFormatter::FontBig, TextFormatter::ColorAmber],
+                    value: "AB".to_string(),
+                },
+                TextSegment {
+                    formatters: vec![TextFormatter::FontSmall, TextFormatter::ColorGreen],
+                    value: "C".to_string(),
+                },
+            ],
+            ..ScreenUpdate::default()
+        };
+        let lines = render_screen(&Grid::from_update(&update, 14, 4));
+
+        assert_eq!(lines.len(), 16);
+        assert_eq!(lines[0], "┌────┐");
+        assert_eq!(
+            lines[14],
+            "│\x1b[0;38;5;214mAB\x1b[0;2;32mC\x1b[0;37m \x1b[0m│"
+        );
+    }
+
+    #[test]
+    fn inverse_and_boxed_cells_are_highlighted() {
+        let update = ScreenUpdate {
+            scratchpad: vec![
+                TextSegment {
+                    formatters: vec![TextFormatter::Inverse],
+                    value: "A".to_string(),
+                },
+                TextSegment {
+                    formatters: vec![TextFormatter::Boxed, TextFormatter::ColorAmber],
+                    value: "  ".to_string(),
+                },
+            ],
+            ..ScreenUpdate::default()
+        };
+        let lines = render_screen(&Grid::from_update(&update, 14, 4));
+
+        assert_eq!(
+            lines[14],
+            "│\x1b[0;7;37mA\x1b[0;4;38;5;214m  \x1b[0;37m \x1b[0m│"
+        );
+    }
+}